@@ -3,11 +3,11 @@ use std::{fs::File, io::BufReader};
 use dashmap::DashMap;
 use image::ImageFormat;
 
-use crate::utils::Color;
+use crate::utils::{Color, Radiance};
 
 #[derive(Debug, Clone)]
 pub struct RTWImage {
-    colors: DashMap<(usize, usize), Color>,
+    colors: DashMap<(usize, usize), Radiance>,
     image_width: usize,
     image_height: usize,
 }
@@ -25,7 +25,8 @@ impl RTWImage {
         let reader = BufReader::new(File::open(image_filename).unwrap());
 
         let image = image::load(reader, format).expect("Cannot read image");
-        let image = image.to_rgb8();
+        // Float data keeps the values above 1.0 in HDR images
+        let image = image.to_rgb32f();
 
         // Loop over the image and populate the dashmap
         let image_height = image.height();
@@ -37,11 +38,11 @@ impl RTWImage {
             for w in 0..image_width {
                 let pixel = image.get_pixel(w, h);
 
-                let r = pixel.0[0] as f64 / 255.0;
-                let g = pixel.0[1] as f64 / 255.0;
-                let b = pixel.0[2] as f64 / 255.0;
+                let r = pixel.0[0] as f64;
+                let g = pixel.0[1] as f64;
+                let b = pixel.0[2] as f64;
 
-                colors.insert((w as usize, h as usize), Color::new(r, g, b));
+                colors.insert((w as usize, h as usize), Radiance::new(r, g, b));
             }
         }
 
@@ -66,7 +67,14 @@ impl RTWImage {
 
     /// Returns the color at an x, y coordinate for the asset. If you are using this
     /// to place a texture you must convert the uv coordinates to x, y coordinates.
+    /// HDR values are clamped, use pixel_radiance to keep them.
     pub fn pixel_data(&self, x: usize, y: usize) -> Color {
+        self.pixel_radiance(x, y).clamp_to_color()
+    }
+
+    /// Returns the unclamped value at an x, y coordinate. This is what
+    /// lights and skyboxes should use so HDR images keep their range.
+    pub fn pixel_radiance(&self, x: usize, y: usize) -> Radiance {
         // Should this be how the library works? It seems weird to fix the pixel coords
         // Maybe it should return none if its out of bounds?
         let x = x.clamp(0, self.image_width - 1);
@@ -1,5 +1,6 @@
 pub mod img_loader;
 pub mod obj_loader;
+pub mod vol_loader;
 
 /// Checks the env variable ASSET_DIR to find where assets are stored. Otherwise searches
 /// for 6 directories up for a folder called assets and the file itself.
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Error, ErrorKind},
+    path::Path,
+};
+
+use crate::objects::voxel_volume::{VoxelGrid, Voxels};
+
+/// Loads a voxel grid from a vol file. Vol files are plain text, a
+/// `#` starts a comment that runs to the end of the line and values
+/// can be split over lines however you like. A file has a header
+/// followed by the voxels:
+///
+/// ```text
+/// # A 2x2x2 puff of smoke
+/// size 2 2 2
+/// channels density
+/// dense
+/// 0.0 1.0 1.0 0.0
+/// 1.0 0.0 0.0 1.0
+/// ```
+///
+/// Size is the number of voxels along x, y and z. Channels is
+/// `density` or `density temperature`, with temperature in Kelvin.
+/// After `dense` comes every voxel with x changing fastest, then y,
+/// then z, each voxel gives a value for every channel in order. After
+/// `sparse` comes only the voxels that are not empty, each as
+/// `x y z density` or `x y z density temperature`. Voxels that are
+/// left out are 0 and a voxel can only be given once.
+///
+/// # Panics
+/// This function panics if it is not a .vol file, if the file is not
+/// openable or if it does not follow the format above.
+pub fn load_vol(file: &str) -> VoxelGrid {
+    let file = super::build_asset_path(file).expect("Could not find asset");
+
+    let file_path = Path::new(file.as_str());
+    if file_path.extension().unwrap() != "vol" {
+        panic!("Expected a vol file.");
+    }
+
+    let vol_file = File::open(file_path).expect("Cannot open vol file.");
+
+    parse_vol(BufReader::new(vol_file)).expect("Cannot read the vol file")
+}
+
+/// Reads a vol file, see load_vol for the format
+///
+/// # Error
+/// Returns an error if a line cannot be read. The error is of kind
+/// InvalidData if the file does not follow the format, has the wrong
+/// number of values, more voxels than fit in memory, a voxel outside
+/// of the grid or given twice, a value that is NaN or infinite or a
+/// negative density.
+fn parse_vol<R: BufRead>(reader: R) -> Result<VoxelGrid, Error> {
+    let mut tokens = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or("");
+        tokens.extend(line.split_whitespace().map(str::to_owned));
+    }
+    let mut tokens = tokens.into_iter();
+
+    expect_keyword(&mut tokens, "size")?;
+    let mut size = [0; 3];
+    for n in size.iter_mut() {
+        *n = parse_value::<usize>(tokens.next(), "a size")?;
+        if *n == 0 {
+            return Err(invalid("Every size has to be at least 1".to_owned()));
+        }
+    }
+    let count = size[0]
+        .checked_mul(size[1])
+        .and_then(|n| n.checked_mul(size[2]))
+        .ok_or_else(|| invalid(format!("A {size:?} grid has too many voxels")))?;
+
+    expect_keyword(&mut tokens, "channels")?;
+    expect_keyword(&mut tokens, "density")?;
+    let (temperature, layout) = match tokens.next().as_deref() {
+        Some("temperature") => (true, tokens.next()),
+        other => (false, other.map(str::to_owned)),
+    };
+    let channels = if temperature { 2 } else { 1 };
+
+    let values: Vec<String> = tokens.collect();
+    let (density, temperature) = match layout.as_deref() {
+        Some("dense") => {
+            let needed = count
+                .checked_mul(channels)
+                .ok_or_else(|| invalid(format!("A {size:?} grid has too many voxels")))?;
+            if values.len() != needed {
+                return Err(invalid(format!(
+                    "A {size:?} grid needs {needed} values but the file has {}",
+                    values.len()
+                )));
+            }
+            let values = values
+                .into_iter()
+                .map(|v| parse_voxel(Some(v)))
+                .collect::<Result<Vec<f32>, Error>>()?;
+
+            let channel = |c: usize| values.iter().skip(c).step_by(channels).copied().collect();
+            let density = Voxels::Dense(channel(0));
+            let temperature = temperature.then(|| Voxels::Dense(channel(1)));
+            (density, temperature)
+        }
+        Some("sparse") => {
+            let per_voxel = 3 + channels;
+            if !values.len().is_multiple_of(per_voxel) {
+                return Err(invalid(format!(
+                    "Every sparse voxel needs {per_voxel} values"
+                )));
+            }
+
+            let mut density = HashMap::new();
+            let mut temperatures = HashMap::new();
+            for voxel in values.chunks(per_voxel) {
+                let mut at = [0; 3];
+                for (i, n) in at.iter_mut().enumerate() {
+                    *n = parse_value::<usize>(Some(&voxel[i]), "a voxel")?;
+                }
+                let [x, y, z] = at;
+                if x >= size[0] || y >= size[1] || z >= size[2] {
+                    return Err(invalid(format!(
+                        "The voxel {x} {y} {z} is outside of the {size:?} grid"
+                    )));
+                }
+
+                let index = x + size[0] * (y + size[1] * z);
+                if density
+                    .insert(index, parse_voxel(Some(&voxel[3]))?)
+                    .is_some()
+                {
+                    return Err(invalid(format!("The voxel {x} {y} {z} is given twice")));
+                }
+                if temperature {
+                    temperatures.insert(index, parse_voxel(Some(&voxel[4]))?);
+                }
+            }
+
+            let temperature = temperature.then_some(Voxels::Sparse(temperatures));
+            (Voxels::Sparse(density), temperature)
+        }
+        other => {
+            return Err(invalid(format!(
+                "Expected `dense` or `sparse` but found {other:?}"
+            )));
+        }
+    };
+
+    if !density.all(|v| v >= 0.0) {
+        return Err(invalid("A density cannot be negative".to_owned()));
+    }
+
+    Ok(VoxelGrid::new(size, density, temperature))
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Invalid vol file. {message}"),
+    )
+}
+
+fn expect_keyword(tokens: &mut impl Iterator<Item = String>, keyword: &str) -> Result<(), Error> {
+    match tokens.next() {
+        Some(token) if token == keyword => Ok(()),
+        token => Err(invalid(format!("Expected `{keyword}` but found {token:?}"))),
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(
+    token: Option<impl AsRef<str>>,
+    what: &str,
+) -> Result<T, Error> {
+    token
+        .and_then(|t| t.as_ref().parse().ok())
+        .ok_or_else(|| invalid(format!("Expected {what}.")))
+}
+
+/// A voxel value, NaN and infinity are not allowed
+fn parse_voxel(token: Option<impl AsRef<str>>) -> Result<f32, Error> {
+    let value: f32 = parse_value(token, "a voxel value")?;
+    if !value.is_finite() {
+        return Err(invalid(format!("{value} is not a voxel value.")));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dense_and_sparse_files_agree() {
+        let dense = "# A hot voxel in a corner
+            size 2 1 1
+            channels density temperature
+            dense
+            0 0   3.5 1500";
+        let sparse = "size 2 1 1 channels density temperature sparse
+            1 0 0 3.5 1500 # The only voxel that is not empty";
+
+        for grid in [dense, sparse] {
+            let grid = parse_vol(grid.as_bytes()).unwrap();
+            assert_eq!(grid.lookup([0.75, 0.5, 0.5]), (3.5, 1500.0));
+            assert_eq!(grid.lookup([0.25, 0.5, 0.5]), (0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn malformed_files_are_invalid_data() {
+        let files = [
+            "",
+            "size 2 1",
+            "size 2 1 1 channels temperature dense 0 0",
+            "size 2 1 1 channels density packed 0 0",
+            "size 2 1 1 channels density dense 0",
+            "size 2 1 1 channels density dense 0 smoke",
+            "size 2 1 1 channels density dense 0 NaN",
+            "size 2 1 1 channels density dense 0 -1",
+            "size 2 1 1 channels density sparse 2 0 0 1",
+            "size 2 1 1 channels density sparse 1 0 0",
+            "size 2 1 1 channels density sparse 1 0 0 1 1 0 0 2",
+            "size 4294967296 4294967296 2 channels density dense 0",
+            "size 4294967296 2147483648 1 channels density temperature dense 0",
+        ];
+
+        for file in files {
+            let error = parse_vol(file.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{file}");
+        }
+    }
+}
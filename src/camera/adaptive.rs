@@ -0,0 +1,76 @@
+/// The 95% confidence z score
+const CONFIDENCE_Z: f64 = 1.96;
+/// Pixels darker than this are judged against it instead of their
+/// own mean, otherwise near black pixels would never converge
+const MIN_MEAN: f64 = 0.01;
+
+/// Settings for adaptive sampling. Every pixel takes min_samples,
+/// after that it keeps sampling in batches of min_samples until its
+/// confidence interval is within noise_threshold of its mean or it
+/// reaches max_samples.
+#[derive(Debug, Clone)]
+pub(super) struct AdaptiveSampling {
+    pub(super) min_samples: u32,
+    pub(super) max_samples: u32,
+    pub(super) noise_threshold: f64,
+}
+
+/// The running mean and variance of a pixels luminance, this uses
+/// Welford's algorithm so the samples do not need to be stored.
+#[derive(Debug, Clone)]
+pub(super) struct PixelVariance {
+    count: u32,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelVariance {
+    pub(super) fn new() -> PixelVariance {
+        PixelVariance {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    pub(super) fn add(&mut self, luminance: f64) {
+        self.count += 1;
+
+        let delta = luminance - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (luminance - self.mean);
+    }
+
+    /// Checks if the 95% confidence interval of the mean is within
+    /// threshold of the mean, relative to its brightness
+    pub(super) fn converged(&self, threshold: f64) -> bool {
+        if self.count < 2 {
+            return false;
+        }
+
+        let n = self.count as f64;
+        let variance = self.m2 / (n - 1.0);
+        let interval = CONFIDENCE_Z * (variance / n).sqrt();
+
+        interval <= threshold * self.mean.max(MIN_MEAN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_pixels_converge() {
+        let mut flat = PixelVariance::new();
+        let mut noisy = PixelVariance::new();
+
+        for i in 0..16 {
+            flat.add(0.6);
+            noisy.add(if i % 2 == 0 { 0.0 } else { 4.0 });
+        }
+
+        assert!(flat.converged(0.01));
+        assert!(!noisy.converged(0.01));
+    }
+}
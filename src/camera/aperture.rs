@@ -0,0 +1,195 @@
+use std::f64::consts::PI;
+
+use crate::{
+    asset_loader::img_loader::RTWImage,
+    utils::{Distribution2D, Point3},
+};
+
+/// The shape of the opening in the lens. Out of focus highlights
+/// take this shape.
+#[derive(Debug, Clone)]
+pub enum ApertureShape {
+    /// A perfect disk, this is the default
+    Circle,
+    /// A regular polygon like the one the blades of a real lens make.
+    /// rotation turns it counterclockwise, in degrees.
+    Polygon { blades: u32, rotation: f64 },
+    /// Any shape drawn in an image, brighter pixels let more light through
+    Mask(ApertureMask),
+}
+
+/// An aperture drawn in an image. The image is stretched over the
+/// lens with its longer side across the whole lens.
+#[derive(Debug, Clone)]
+pub struct ApertureMask {
+    /// Picks the pixels in proportion to their brightness
+    distribution: Distribution2D,
+}
+
+impl ApertureMask {
+    /// Builds a mask from the brightness of an image, see `RTWImage::new`
+    /// for where the image is loaded from.
+    ///
+    /// # Panics:
+    /// This panics if the image is completely black.
+    pub fn new(image: &RTWImage) -> ApertureMask {
+        let (width, height) = (image.width(), image.height());
+        let weights = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| image.pixel_radiance(x, y).luminance().max(0.0))
+            .collect();
+
+        ApertureMask::from_weights(width, height, weights)
+    }
+
+    /// Loads the image in the assets folder and builds a mask from it
+    pub fn from_file(image_filename: &str) -> ApertureMask {
+        ApertureMask::new(&RTWImage::new(image_filename))
+    }
+
+    fn from_weights(width: usize, height: usize, weights: Vec<f64>) -> ApertureMask {
+        let distribution = Distribution2D::new(width, height, weights)
+            .expect("The aperture mask lets no light through.");
+
+        ApertureMask { distribution }
+    }
+
+    /// Maps a 2D sample to a point of the mask, picked in proportion
+    /// to its brightness. The point is in [-1, 1] on the longer side.
+    fn sample(&self, u: (f64, f64)) -> (f64, f64) {
+        let (x, y) = self.distribution.sample(u);
+        let (width, height) = (
+            self.distribution.width() as f64,
+            self.distribution.height() as f64,
+        );
+
+        let size = width.max(height);
+        let px = (2.0 * x - width) / size;
+        let py = (height - 2.0 * y) / size;
+
+        (px, py)
+    }
+}
+
+/// The lens opening of a camera, on a lens of radius 1
+#[derive(Debug, Clone)]
+pub(super) struct Aperture {
+    pub(super) shape: ApertureShape,
+    /// Anamorphic lenses squeeze the aperture horizontally
+    pub(super) squeeze: f64,
+    /// How much the lens barrel cuts off the aperture near the
+    /// edges of the frame, from 0 to 1
+    pub(super) cat_eye: f64,
+}
+
+impl Default for Aperture {
+    fn default() -> Self {
+        Aperture {
+            shape: ApertureShape::Circle,
+            squeeze: 1.0,
+            cat_eye: 0.0,
+        }
+    }
+}
+
+impl Aperture {
+    /// Maps a 2D sample to a point on the lens. image_pos is where the
+    /// ray goes through the frame, (0, 0) at the center and 1 at the
+    /// corners. Returns None if the lens barrel blocks the point.
+    pub(super) fn sample(&self, u: (f64, f64), image_pos: (f64, f64)) -> Option<(f64, f64)> {
+        let (x, y) = match &self.shape {
+            ApertureShape::Circle => {
+                let p = Point3::in_unit_disk_from_sample(u);
+                (p.x(), p.y())
+            }
+            ApertureShape::Polygon { blades, rotation } => {
+                polygon_sample(*blades, rotation.to_radians(), u)
+            }
+            ApertureShape::Mask(mask) => mask.sample(u),
+        };
+
+        // The barrel is a second opening, shifted further off center
+        // the further the ray is from the middle of the frame. Without
+        // cat eye there is no barrel, masks can reach past the circle.
+        let shift = (self.cat_eye * image_pos.0, self.cat_eye * image_pos.1);
+        if self.cat_eye > 0.0 && (x - shift.0).powi(2) + (y - shift.1).powi(2) > 1.0 {
+            return None;
+        }
+
+        Some((x / self.squeeze, y))
+    }
+}
+
+/// Uniformly samples a regular polygon with its corners on the unit
+/// circle. The first number picks one of the triangles around the
+/// center, the rest of both numbers place the point inside it.
+fn polygon_sample(blades: u32, rotation: f64, u: (f64, f64)) -> (f64, f64) {
+    let n = blades as f64;
+    let scaled = u.0 * n;
+    let sector = scaled.floor().min(n - 1.0);
+    let a = scaled - sector;
+
+    let corner = |k: f64| {
+        let angle = rotation + PI / 2.0 + 2.0 * PI * k / n;
+        (angle.cos(), angle.sin())
+    };
+    let (c0, c1) = (corner(sector), corner(sector + 1.0));
+
+    // Uniform in the triangle (center, c0, c1)
+    let s = a.sqrt();
+    let (b0, b1) = (s * (1.0 - u.1), s * u.1);
+
+    (b0 * c0.0 + b1 * c1.0, b0 * c0.1 + b1 * c1.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygon_stays_inside() {
+        // A square with its corners on the axes
+        for k in 0..64 {
+            let u = ((k % 8) as f64 / 8.0 + 0.01, (k / 8) as f64 / 8.0 + 0.01);
+            let (x, y) = polygon_sample(4, 0.0, u);
+
+            assert!(x.abs() + y.abs() <= 1.0 + 1e-12);
+        }
+    }
+
+    #[test]
+    fn mask_follows_brightness() {
+        // Only the right column of a 2x2 image lets light through
+        let mask = ApertureMask::from_weights(2, 2, vec![0.0, 1.0, 0.0, 3.0]);
+
+        for k in 0..16 {
+            let (x, y) = mask.sample((k as f64 / 16.0, (k as f64 + 0.5) / 16.0));
+
+            assert!(x >= 0.0);
+            // The bottom pixel is three times as bright
+            assert_eq!(y < 0.0, (k as f64 + 0.5) / 16.0 >= 0.25);
+        }
+    }
+
+    #[test]
+    fn cat_eye_blocks_edges() {
+        let aperture = Aperture {
+            cat_eye: 1.0,
+            ..Aperture::default()
+        };
+
+        assert!(aperture.sample((0.0, 0.5), (0.0, 0.0)).is_some());
+        assert!(aperture.sample((0.0, 0.5), (1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn square_mask_keeps_corners() {
+        let aperture = Aperture {
+            shape: ApertureShape::Mask(ApertureMask::from_weights(1, 1, vec![1.0])),
+            ..Aperture::default()
+        };
+
+        let (x, y) = aperture.sample((0.99, 0.01), (1.0, 1.0)).unwrap();
+        assert!(x > 0.95 && y > 0.95);
+    }
+}
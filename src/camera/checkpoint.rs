@@ -0,0 +1,248 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Error, ErrorKind, Read, Write},
+};
+
+use crate::{
+    camera::{
+        PixelFilter, SamplingMethod,
+        framebuffer::Framebuffer,
+        tiles::{Tile, TileOrder},
+    },
+    utils::Radiance,
+};
+
+/// The first bytes of every checkpoint file
+const MAGIC: &[u8; 8] = b"CRUCKPT3";
+/// How many numbers the settings are saved as
+const KEY_FIELDS: usize = 20;
+
+/// Where the checkpoint of the image fname is kept
+pub(crate) fn checkpoint_path(fname: &str) -> String {
+    fname.to_owned() + ".ckpt"
+}
+
+/// The settings a checkpoint was rendered with. A checkpoint can
+/// only be resumed by a render with the same settings, otherwise
+/// the saved samples would not belong to the image.
+///
+/// A checkpoint holds whole tiles with all of their samples, so
+/// resuming only renders the tiles that are missing. It cannot add
+/// samples to tiles that are done, a render with more samples has
+/// a different key and starts over.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RenderKey {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    /// The pixels that are sampled, all of them unless there is
+    /// a crop window
+    pub(super) region: Tile,
+    pub(super) frame: u64,
+    pub(super) seed: u64,
+    /// The most samples a pixel takes, this is the sample count
+    /// unless adaptive sampling is on
+    pub(super) samples: u32,
+    pub(super) min_samples: u32,
+    pub(super) noise_threshold: f64,
+    pub(super) sampling_method: SamplingMethod,
+    pub(super) filter: PixelFilter,
+    pub(super) filter_radius: f64,
+    pub(super) tile_size: u32,
+    pub(super) tile_order: TileOrder,
+    pub(super) tile_count: u64,
+    /// See Integrators::key
+    pub(super) integrator: [u64; 3],
+}
+
+impl RenderKey {
+    fn fields(&self) -> [u64; KEY_FIELDS] {
+        [
+            self.width as u64,
+            self.height as u64,
+            self.region.x0 as u64,
+            self.region.y0 as u64,
+            self.region.x1 as u64,
+            self.region.y1 as u64,
+            self.frame,
+            self.seed,
+            self.samples as u64,
+            self.min_samples as u64,
+            self.noise_threshold.to_bits(),
+            self.sampling_method.clone() as u64,
+            self.filter.clone() as u64,
+            self.filter_radius.to_bits(),
+            self.tile_size as u64,
+            self.tile_order.clone() as u64,
+            self.tile_count,
+            self.integrator[0],
+            self.integrator[1],
+            self.integrator[2],
+        ]
+    }
+}
+
+/// A partly rendered image. Tiles are merged in order, so the
+/// first tiles_done tiles are in the framebuffer and the rest
+/// still have to be rendered.
+#[derive(Debug)]
+pub(super) struct Checkpoint {
+    pub(super) tiles_done: usize,
+    pub(super) framebuffer: Framebuffer,
+}
+
+/// Saves the weighted sums of the framebuffer to path. The file is
+/// written next to path and then moved over it, so being stopped
+/// halfway leaves the last checkpoint intact.
+///
+/// # Error
+/// Returns an error if the file cannot be written.
+pub(super) fn save(
+    path: &str,
+    key: &RenderKey,
+    tiles_done: usize,
+    framebuffer: &Framebuffer,
+) -> Result<(), Error> {
+    let partial = path.to_owned() + ".partial";
+    let mut w = BufWriter::new(File::create(&partial)?);
+
+    w.write_all(MAGIC)?;
+    for field in key.fields() {
+        w.write_all(&field.to_le_bytes())?;
+    }
+    w.write_all(&(tiles_done as u64).to_le_bytes())?;
+
+    let (sums, weights) = framebuffer.accumulated();
+    for (sum, weight) in sums.iter().zip(weights) {
+        for value in [sum.r(), sum.g(), sum.b(), *weight] {
+            w.write_all(&value.to_le_bytes())?;
+        }
+    }
+
+    w.flush()?;
+    drop(w);
+    fs::rename(partial, path)
+}
+
+/// Loads the checkpoint at path
+///
+/// # Error
+/// Returns an error if the file cannot be read, is not a checkpoint,
+/// was rendered with different settings than key or claims more
+/// tiles than the render has.
+pub(super) fn load(path: &str, key: &RenderKey) -> Result<Checkpoint, Error> {
+    let mut r = BufReader::new(File::open(path)?);
+
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{path} is not a checkpoint"),
+        ));
+    }
+
+    let mut saved = [0; KEY_FIELDS];
+    for field in saved.iter_mut() {
+        *field = read_u64(&mut r)?;
+    }
+    if saved != key.fields() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{path} was rendered with different settings"),
+        ));
+    }
+    let tiles_done = read_u64(&mut r)?;
+    if tiles_done > key.tile_count {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{path} has more tiles than the render"),
+        ));
+    }
+    let tiles_done = tiles_done as usize;
+
+    let size = key.width as usize * key.height as usize;
+    let mut sums = Vec::with_capacity(size);
+    let mut weights = Vec::with_capacity(size);
+    for _ in 0..size {
+        let r_sum = read_f64(&mut r)?;
+        let g_sum = read_f64(&mut r)?;
+        let b_sum = read_f64(&mut r)?;
+        sums.push(Radiance::new(r_sum, g_sum, b_sum));
+        weights.push(read_f64(&mut r)?);
+    }
+
+    Ok(Checkpoint {
+        tiles_done,
+        framebuffer: Framebuffer::from_accumulated(key.width, key.height, sums, weights),
+    })
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64, Error> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f64<R: Read>(r: &mut R) -> Result<f64, Error> {
+    Ok(f64::from_bits(read_u64(r)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Integrators;
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let key = RenderKey {
+            width: 3,
+            height: 2,
+            region: Tile::new(0, 0, 3, 2),
+            frame: 4,
+            seed: 9,
+            samples: 16,
+            min_samples: 16,
+            noise_threshold: 0.0,
+            sampling_method: SamplingMethod::Sobol,
+            filter: PixelFilter::Gaussian,
+            filter_radius: 1.5,
+            tile_size: 32,
+            tile_order: TileOrder::Hilbert,
+            tile_count: 1,
+            integrator: Integrators::default().key(),
+        };
+        let mut fb = Framebuffer::new(3, 2);
+        fb.set_pixel(2, 1, Radiance::new(7.5, 0.25, 1e-3));
+
+        let path = std::env::temp_dir().join(format!("crucible_{}.ckpt", std::process::id()));
+        let path = path.to_str().unwrap();
+        save(path, &key, 1, &fb).unwrap();
+
+        let loaded = load(path, &key).unwrap();
+        assert_eq!(loaded.tiles_done, 1);
+        assert_eq!(loaded.framebuffer.pixels(), fb.pixels());
+
+        let other = RenderKey {
+            seed: 10,
+            ..key.clone()
+        };
+        assert!(load(path, &other).is_err());
+        let other = RenderKey {
+            tile_order: TileOrder::Spiral,
+            ..key.clone()
+        };
+        assert!(load(path, &other).is_err());
+        let other = RenderKey {
+            integrator: Integrators::Normals.key(),
+            ..key.clone()
+        };
+        assert!(load(path, &other).is_err());
+
+        // A broken file cannot claim more tiles than there are
+        save(path, &key, 2, &fb).unwrap();
+        let error = load(path, &key).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,51 +1,67 @@
 use std::{
-    sync::{Arc, Mutex, RwLock, mpsc},
+    sync::{Arc, Mutex, mpsc},
     thread::{self, JoinHandle},
 };
 
-use dashmap::DashMap;
 use indicatif::ProgressBar;
 
-use crate::{camera::Camera, objects::Hittables, scene::Skybox, utils::Color};
+use crate::{
+    camera::{Camera, RenderWorld, filter::TileFilm, tiles::Tile},
+    lights::LightList,
+    objects::Hittables,
+    scene::Skybox,
+};
 
 /// Contains information to be sent to a thread
-/// at runtime
+/// at runtime. Each message is a whole tile of
+/// the image for the thread to render.
 pub struct ThreadInfo {
-    i: u32,
-    j: u32,
+    index: usize,
+    tile: Tile,
 }
 
 impl ThreadInfo {
-    pub(super) fn new(i: u32, j: u32) -> ThreadInfo {
-        ThreadInfo { i, j }
+    pub(super) fn new(index: usize, tile: Tile) -> ThreadInfo {
+        ThreadInfo { index, tile }
     }
 }
 
+/// A finished tile sent back from a thread, holding
+/// the filtered samples of the tile and its apron.
+/// index is the position of the tile in the render order.
+pub struct TileResult {
+    pub(super) index: usize,
+    pub(super) film: TileFilm,
+}
+
 impl Camera {
     pub(super) fn thread_setup(
         &self,
         skybox: &Skybox,
-        world: &Hittables,
-    ) -> (Vec<JoinHandle<()>>, Option<mpsc::Sender<ThreadInfo>>) {
+        world: Arc<Hittables>,
+        lights: &LightList,
+        pixel_count: u64,
+    ) -> (
+        Vec<JoinHandle<()>>,
+        Option<mpsc::Sender<ThreadInfo>>,
+        mpsc::Receiver<TileResult>,
+    ) {
         // rendering environment
 
-        let arc_skybox = Arc::new(skybox.clone());
+        let world = Arc::new(RenderWorld::new(world, lights.clone(), skybox.clone()));
         let arc_cam = Arc::new(self.clone());
 
         // Channels
         let (sender, receiver) = mpsc::channel();
         let receiver = Arc::new(Mutex::new(receiver));
+        let (result_sender, result_receiver) = mpsc::channel();
 
         // start threads
         let mut threads = Vec::with_capacity(self.thread_count);
 
         for id in 0..self.thread_count {
-            // This is bad for efficiency but since each thread can have different times they all need a copy of the world
-            let clone_world = world.clone();
-
             // Make progress bar for thread
-            let work = (self.viewport.image_height * self.viewport.image_width) as u64
-                / self.thread_count as u64;
+            let work = pixel_count / self.thread_count as u64;
             let pb = self.mp.add(ProgressBar::new(work));
             pb.set_style(self.sty.clone());
 
@@ -54,14 +70,13 @@ impl Camera {
                 pb,
                 id,
                 Arc::clone(&receiver),
-                Arc::clone(&self.results),
+                result_sender.clone(),
                 Arc::clone(&arc_cam),
-                Arc::clone(&arc_skybox),
-                Arc::new(RwLock::new(clone_world)),
+                Arc::clone(&world),
             ));
         }
 
-        (threads, Some(sender))
+        (threads, Some(sender), result_receiver)
     }
 }
 
@@ -70,40 +85,39 @@ pub fn start_thread_cpu(
     pb: ProgressBar,
     id: usize,
     receiver: Arc<Mutex<mpsc::Receiver<ThreadInfo>>>,
-    results: Arc<DashMap<(u32, u32), Color>>,
+    results: mpsc::Sender<TileResult>,
     cam: Arc<Camera>,
-    skybox: Arc<Skybox>,
-    world: Arc<RwLock<Hittables>>,
+    world: Arc<RenderWorld>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         let id = id;
         let mut progress = 0;
 
-        let cam = Box::new(cam);
-        let mut world = Box::new(world);
-
         loop {
             let message = receiver.lock().unwrap().recv();
 
             match message {
                 Ok(info) => {
-                    let thread_loc_i = info.i;
-                    let thread_loc_j = info.j;
-
-                    let color = cam.cast_ray(
-                        thread_loc_i,
-                        thread_loc_j,
-                        cam.max_depth,
-                        &skybox,
-                        Arc::get_mut(&mut world).unwrap().get_mut().unwrap(),
-                    );
-
-                    results.insert((thread_loc_i, thread_loc_j), color);
-                    if progress % 10 == 0 {
-                        pb.set_message(format!("t{id}"));
-                        pb.inc(10);
+                    let tile = info.tile;
+                    let (width, height) = (cam.viewport.image_width, cam.viewport.image_height);
+                    let mut film = TileFilm::new(&tile, &cam.filter, width, height);
+
+                    for j in tile.y0..tile.y1 {
+                        for i in tile.x0..tile.x1 {
+                            cam.cast_ray(i, j, &world, &mut film);
+                        }
                     }
+
+                    pb.set_message(format!("t{id} tile{progress}"));
+                    pb.inc(tile.pixel_count());
                     progress += 1;
+
+                    results
+                        .send(TileResult {
+                            index: info.index,
+                            film,
+                        })
+                        .unwrap();
                 }
                 Err(_) => {
                     pb.finish_and_clear();
@@ -0,0 +1,61 @@
+use crate::camera::tiles::Tile;
+
+/// What a render with a crop window saves
+#[derive(Debug, Clone, PartialEq)]
+pub enum CropOutput {
+    /// An image the size of the crop window
+    Cropped,
+    /// A full size image that is black outside of the crop window
+    FullFrame,
+}
+
+/// A rectangle of the image to render instead of the whole frame.
+/// The end coordinates are exclusive.
+#[derive(Debug, Clone)]
+pub(super) struct CropWindow {
+    pub(super) region: Tile,
+    pub(super) output: CropOutput,
+}
+
+impl CropWindow {
+    /// The pixels that have to be sampled for the window to match the
+    /// full frame. Samples spread apron pixels past where they are
+    /// taken, so the pixels on the border of the window need samples
+    /// from just outside of it.
+    pub(super) fn sampled_region(&self, apron: u32, width: u32, height: u32) -> Tile {
+        Tile::new(
+            self.region.x0.saturating_sub(apron),
+            self.region.y0.saturating_sub(apron),
+            (self.region.x1 + apron).min(width),
+            (self.region.y1 + apron).min(height),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::tests::{render_tiny, tiny_camera};
+
+    #[test]
+    fn crop_matches_full_frame() {
+        let full = render_tiny(&mut tiny_camera(1, 3), "crop_full");
+
+        let mut cam = tiny_camera(1, 3);
+        cam.set_crop_window(7, 9, 15, 12, CropOutput::Cropped);
+        let cropped = render_tiny(&mut cam, "crop_window");
+        assert_eq!(cropped.len(), 8 * 3);
+
+        for (k, pixel) in cropped.iter().enumerate() {
+            let (i, j) = (7 + k % 8, 9 + k / 8);
+            let expected = full[j * 24 + i];
+
+            assert!((pixel.0 - expected.0).abs() < 1e-9, "({i}, {j}) differs");
+        }
+
+        cam.set_crop_window(7, 9, 15, 12, CropOutput::FullFrame);
+        let framed = render_tiny(&mut cam, "crop_framed");
+        assert_eq!(framed[0], (0.0, 0.0, 0.0));
+        assert_eq!(framed[9 * 24 + 7], cropped[0]);
+    }
+}
@@ -0,0 +1,195 @@
+use std::f64::consts::PI;
+
+use clap::ValueEnum;
+
+use crate::{camera::tiles::Tile, utils::Radiance};
+
+/// The shapes a sample's weight can take as it spreads to the
+/// pixels around it. Every filter is separable.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum PixelFilter {
+    /// Every sample inside the radius counts the same. With a radius
+    /// of 0.5 this is a plain average of the samples in the pixel.
+    Box,
+    /// Weight falls off linearly to zero at the radius
+    Tent,
+    /// A gaussian with a standard deviation of a third of the radius
+    Gaussian,
+    /// Mitchell-Netravali with B = C = 1/3, sharp with little ringing
+    Mitchell,
+    /// A sinc windowed by a wider sinc, the sharpest of the filters
+    Lanczos,
+}
+
+impl PixelFilter {
+    /// Picks a filter from a name such as "gaussian", this is meant
+    /// for command line flags. Returns None if the name is not a filter.
+    pub fn from_name(name: &str) -> Option<PixelFilter> {
+        PixelFilter::from_str(name, true).ok()
+    }
+}
+
+/// A pixel filter with the distance it reaches in pixels
+#[derive(Debug, Clone)]
+pub(super) struct Filter {
+    pub(super) kind: PixelFilter,
+    pub(super) radius: f64,
+}
+
+impl Filter {
+    /// How many pixels past a tile its samples can reach
+    pub(super) fn apron(&self) -> u32 {
+        (self.radius + 0.5).ceil() as u32
+    }
+
+    /// The weight of a sample that is dx, dy pixels from a pixel center
+    pub(super) fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, d: f64) -> f64 {
+        let r = self.radius;
+
+        // Half open so a sample on the border of two box pixels
+        // only lands in one of them
+        if d <= -r || d > r {
+            return 0.0;
+        }
+
+        match self.kind {
+            PixelFilter::Box => 1.0,
+            PixelFilter::Tent => r - d.abs(),
+            PixelFilter::Gaussian => {
+                let sigma = r / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+
+                // Shift down so the weight reaches zero at the radius
+                (gaussian(d) - gaussian(r)).max(0.0)
+            }
+            PixelFilter::Mitchell => mitchell(2.0 * d.abs() / r),
+            PixelFilter::Lanczos => sinc(d) * sinc(d / r),
+        }
+    }
+}
+
+/// The Mitchell-Netravali cubic over [0, 2]
+fn mitchell(x: f64) -> f64 {
+    let b = 1.0 / 3.0;
+    let c = 1.0 / 3.0;
+
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+            + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+            + (6.0 - 2.0 * b)
+    } else if x < 2.0 {
+        (-b - 6.0 * c) * x.powi(3)
+            + (6.0 * b + 30.0 * c) * x.powi(2)
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+
+    value / 6.0
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// The weighted samples a thread gathered for one tile. The film
+/// covers the tile plus an apron, since samples near the edge of
+/// the tile also count towards the pixels just outside of it.
+#[derive(Debug, Clone)]
+pub(super) struct TileFilm {
+    pub(super) region: Tile,
+    pub(super) sums: Vec<Radiance>,
+    pub(super) weights: Vec<f64>,
+}
+
+impl TileFilm {
+    /// Makes an empty film for the tile, the apron is clipped
+    /// to the image
+    pub(super) fn new(tile: &Tile, filter: &Filter, width: u32, height: u32) -> TileFilm {
+        let apron = filter.apron();
+        let region = Tile::new(
+            tile.x0.saturating_sub(apron),
+            tile.y0.saturating_sub(apron),
+            (tile.x1 + apron).min(width),
+            (tile.y1 + apron).min(height),
+        );
+        let size = region.pixel_count() as usize;
+
+        TileFilm {
+            region,
+            sums: vec![Radiance::black(); size],
+            weights: vec![0.0; size],
+        }
+    }
+
+    /// Spreads a sample taken at (x, y) to every pixel the filter
+    /// reaches. Pixel centers are at whole numbers.
+    pub(super) fn add_sample(&mut self, x: f64, y: f64, radiance: &Radiance, filter: &Filter) {
+        let r = filter.radius;
+        let region = &self.region;
+
+        let x_start = ((x - r).floor().max(region.x0 as f64)) as u32;
+        let x_end = ((x + r).floor().min(region.x1 as f64 - 1.0)) as i64;
+        let y_start = ((y - r).floor().max(region.y0 as f64)) as u32;
+        let y_end = ((y + r).floor().min(region.y1 as f64 - 1.0)) as i64;
+
+        for j in y_start as i64..=y_end {
+            for i in x_start as i64..=x_end {
+                let weight = filter.weight(i as f64 - x, j as f64 - y);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let index = (j as u32 - region.y0) as usize * region.width() as usize
+                    + (i as u32 - region.x0) as usize;
+                self.sums[index] += radiance.clone() * weight;
+                self.weights[index] += weight;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_box_stays_in_pixel() {
+        let filter = Filter {
+            kind: PixelFilter::Box,
+            radius: 0.5,
+        };
+        let mut film = TileFilm::new(&Tile::new(0, 0, 3, 3), &filter, 3, 3);
+
+        // On the left edge of pixel (1, 1)
+        film.add_sample(0.5, 1.49, &Radiance::new(1.0, 0.0, 0.0), &filter);
+
+        let hit: Vec<usize> = (0..9).filter(|&k| film.weights[k] != 0.0).collect();
+        assert_eq!(hit, vec![4]);
+    }
+
+    #[test]
+    fn filters_peak_at_center() {
+        for kind in [
+            PixelFilter::Tent,
+            PixelFilter::Gaussian,
+            PixelFilter::Mitchell,
+            PixelFilter::Lanczos,
+        ] {
+            let filter = Filter { kind, radius: 2.0 };
+
+            assert!(filter.weight(0.0, 0.0) > filter.weight(0.7, 0.0));
+            assert!(filter.weight(0.7, 0.0) > filter.weight(0.7, 0.7));
+            assert_eq!(filter.weight(2.5, 0.0), 0.0);
+        }
+    }
+}
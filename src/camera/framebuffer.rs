@@ -0,0 +1,376 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Error, Write},
+};
+
+use image::{ImageBuffer, ImageFormat, Rgb, Rgb32FImage, RgbImage};
+
+use crate::{
+    camera::{
+        filter::TileFilm, output_format::OutputFormat, tiles::Tile, tone_mapping::ToneMapping,
+    },
+    utils::Radiance,
+};
+
+/// Pixels with less total weight than this have no estimate. A
+/// single sample at a pixel's center weighs about 1 with any filter.
+const MIN_WEIGHT: f64 = 1e-3;
+
+/// A preallocated image stored row by row in one contiguous
+/// buffer. The camera renders into this and it stays readable
+/// after the render so the image can be used in memory. Pixels
+/// hold linear radiance, tone mapping only happens on save.
+///
+/// Filtered samples are kept as weighted sums, each pixel is the
+/// sum divided by the total weight that reached it.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Radiance>,
+    sums: Vec<Radiance>,
+    weights: Vec<f64>,
+}
+
+impl Framebuffer {
+    /// Makes a black framebuffer with width x height pixels
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        let size = width as usize * height as usize;
+
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Radiance::black(); size],
+            sums: vec![Radiance::black(); size],
+            weights: vec![0.0; size],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// All of the pixels, left to right and top to bottom
+    pub fn pixels(&self) -> &[Radiance] {
+        &self.pixels
+    }
+
+    /// Returns the radiance of the pixel in column i and row j
+    ///
+    /// # Panics
+    /// Panics if i or j is outside of the image.
+    pub fn get_pixel(&self, i: u32, j: u32) -> &Radiance {
+        &self.pixels[self.index(i, j)]
+    }
+
+    /// Sets the radiance of the pixel in column i and row j. This
+    /// replaces any samples the pixel had.
+    ///
+    /// # Panics
+    /// Panics if i or j is outside of the image.
+    pub fn set_pixel(&mut self, i: u32, j: u32, color: Radiance) {
+        let index = self.index(i, j);
+        self.pixels[index] = color.clone();
+        self.sums[index] = color;
+        self.weights[index] = 1.0;
+    }
+
+    /// Rebuilds a buffer from weighted sums, such as the ones saved in
+    /// a checkpoint. Both vectors must hold width * height pixels.
+    pub(super) fn from_accumulated(
+        width: u32,
+        height: u32,
+        sums: Vec<Radiance>,
+        weights: Vec<f64>,
+    ) -> Framebuffer {
+        let size = width as usize * height as usize;
+        assert!(sums.len() == size && weights.len() == size);
+
+        let mut fb = Framebuffer {
+            width,
+            height,
+            pixels: vec![Radiance::black(); size],
+            sums,
+            weights,
+        };
+        for index in 0..size {
+            fb.pixels[index] = fb.resolve(index);
+        }
+
+        fb
+    }
+
+    /// The weighted sum and the total weight of every pixel
+    pub(super) fn accumulated(&self) -> (&[Radiance], &[f64]) {
+        (&self.sums, &self.weights)
+    }
+
+    /// Adds the weighted samples of a rendered tile to the buffer and
+    /// updates the pixels it covers. Tiles overlap at their aprons so
+    /// the order they are merged in only changes the float rounding.
+    pub(super) fn merge_tile(&mut self, film: TileFilm) {
+        let region = &film.region;
+        let mut samples = film.sums.into_iter().zip(film.weights);
+
+        for j in region.y0..region.y1 {
+            for i in region.x0..region.x1 {
+                let (sum, weight) = samples.next().unwrap();
+                let index = self.index(i, j);
+
+                self.sums[index] += sum;
+                self.weights[index] += weight;
+                self.pixels[index] = self.resolve(index);
+            }
+        }
+    }
+
+    /// Negative lobed filters can leave a slightly negative sum
+    /// at hard edges, that is clipped to black. They can also leave
+    /// a pixel at the edge of the image with a weight near zero or
+    /// below it, dividing by that would blow the pixel up so it is
+    /// black as well.
+    fn resolve(&self, index: usize) -> Radiance {
+        let weight = self.weights[index];
+        if weight <= MIN_WEIGHT {
+            return Radiance::black();
+        }
+
+        let pixel = self.sums[index].clone() / weight;
+        Radiance::new(pixel.r().max(0.0), pixel.g().max(0.0), pixel.b().max(0.0))
+    }
+
+    /// Saves the buffer to fname in the given format. Note
+    /// that this will truncate the file. The 8 and 16 bit formats
+    /// go through the whole tone mapping, the float formats only
+    /// get the exposure.
+    ///
+    /// # Error
+    /// Returns an error if the file cannot be written or encoded.
+    pub fn save(
+        &self,
+        fname: &str,
+        format: &OutputFormat,
+        tone_mapping: &ToneMapping,
+    ) -> Result<(), Error> {
+        match format {
+            OutputFormat::Ppm => {
+                let f = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(fname)?;
+
+                let mut bw = BufWriter::new(f);
+                self.write_ppm(&mut bw, tone_mapping)?;
+                bw.flush()
+            }
+            OutputFormat::Png8 => {
+                let image = RgbImage::from_fn(self.width, self.height, |i, j| {
+                    let (r, g, b) = tone_mapping.display_channels(self.get_pixel(i, j));
+                    Rgb([(255.0 * r) as u8, (255.0 * g) as u8, (255.0 * b) as u8])
+                });
+
+                image
+                    .save_with_format(fname, ImageFormat::Png)
+                    .map_err(Error::other)
+            }
+            OutputFormat::Png16 => {
+                let image: ImageBuffer<Rgb<u16>, Vec<u16>> =
+                    ImageBuffer::from_fn(self.width, self.height, |i, j| {
+                        let (r, g, b) = tone_mapping.display_channels(self.get_pixel(i, j));
+                        Rgb([
+                            (65535.0 * r) as u16,
+                            (65535.0 * g) as u16,
+                            (65535.0 * b) as u16,
+                        ])
+                    });
+
+                image
+                    .save_with_format(fname, ImageFormat::Png)
+                    .map_err(Error::other)
+            }
+            OutputFormat::Exr => self
+                .to_linear_image(tone_mapping)
+                .save_with_format(fname, ImageFormat::OpenExr)
+                .map_err(Error::other),
+            OutputFormat::Hdr => self
+                .to_linear_image(tone_mapping)
+                .save_with_format(fname, ImageFormat::Hdr)
+                .map_err(Error::other),
+        }
+    }
+
+    /// Copies the exposed buffer into a float image with no gamma
+    fn to_linear_image(&self, tone_mapping: &ToneMapping) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width, self.height, |i, j| {
+            let color = tone_mapping.expose(self.get_pixel(i, j));
+            Rgb([color.r() as f32, color.g() as f32, color.b() as f32])
+        })
+    }
+
+    /// Writes the buffer as an ASCII P3 ppm image
+    pub fn write_ppm<W: Write>(&self, w: &mut W, tone_mapping: &ToneMapping) -> Result<(), Error> {
+        writeln!(w, "P3\n{} {}\n255", self.width, self.height)?;
+
+        for radiance in self.pixels.iter() {
+            let (r, g, b) = tone_mapping.display_channels(radiance);
+            writeln!(
+                w,
+                "{} {} {}",
+                (255.0 * r) as u32,
+                (255.0 * g) as u32,
+                (255.0 * b) as u32
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies the pixels inside region into a buffer of their own
+    pub(super) fn crop(&self, region: &Tile) -> Framebuffer {
+        let mut cropped = Framebuffer::new(region.width(), region.height());
+
+        for j in region.y0..region.y1 {
+            for i in region.x0..region.x1 {
+                let from = self.index(i, j);
+                let to = cropped.index(i - region.x0, j - region.y0);
+
+                cropped.pixels[to] = self.pixels[from].clone();
+                cropped.sums[to] = self.sums[from].clone();
+                cropped.weights[to] = self.weights[from];
+            }
+        }
+
+        cropped
+    }
+
+    /// Copies every pixel of other into this buffer, with the top
+    /// left corner of other at (x0, y0)
+    pub(super) fn paste(&mut self, other: &Framebuffer, x0: u32, y0: u32) {
+        for j in 0..other.height {
+            for i in 0..other.width {
+                let from = other.index(i, j);
+                let to = self.index(x0 + i, y0 + j);
+
+                self.pixels[to] = other.pixels[from].clone();
+                self.sums[to] = other.sums[from].clone();
+                self.weights[to] = other.weights[from];
+            }
+        }
+    }
+
+    /// Blacks out every pixel outside of region
+    pub(super) fn clear_outside(&mut self, region: &Tile) {
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let inside =
+                    (region.x0..region.x1).contains(&i) && (region.y0..region.y1).contains(&j);
+                if !inside {
+                    let index = self.index(i, j);
+                    self.pixels[index] = Radiance::black();
+                    self.sums[index] = Radiance::black();
+                    self.weights[index] = 0.0;
+                }
+            }
+        }
+    }
+
+    fn index(&self, i: u32, j: u32) -> usize {
+        assert!(
+            i < self.width && j < self.height,
+            "Pixel ({i}, {j}) is outside of the {}x{} framebuffer",
+            self.width,
+            self.height
+        );
+
+        j as usize * self.width as usize + i as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{
+        filter::{Filter, PixelFilter},
+        tiles::Tile,
+    };
+
+    #[test]
+    fn tile_lands_in_place() {
+        let mut fb = Framebuffer::new(4, 3);
+        let filter = Filter {
+            kind: PixelFilter::Box,
+            radius: 0.5,
+        };
+        let tile = Tile::new(1, 1, 3, 3);
+        let mut film = TileFilm::new(&tile, &filter, 4, 3);
+
+        film.add_sample(1.0, 1.0, &Radiance::new(0.1, 0.0, 0.0), &filter);
+        film.add_sample(2.0, 1.0, &Radiance::new(0.2, 0.0, 0.0), &filter);
+        film.add_sample(1.0, 2.0, &Radiance::new(0.3, 0.0, 0.0), &filter);
+        film.add_sample(2.0, 2.0, &Radiance::new(0.4, 0.0, 0.0), &filter);
+
+        fb.merge_tile(film);
+
+        assert_eq!(fb.get_pixel(0, 0), &Radiance::black());
+        assert_eq!(fb.get_pixel(1, 1), &Radiance::new(0.1, 0.0, 0.0));
+        assert_eq!(fb.get_pixel(2, 1), &Radiance::new(0.2, 0.0, 0.0));
+        assert_eq!(fb.get_pixel(1, 2), &Radiance::new(0.3, 0.0, 0.0));
+        assert_eq!(fb.get_pixel(2, 2), &Radiance::new(0.4, 0.0, 0.0));
+        assert_eq!(fb.pixels()[6], Radiance::new(0.2, 0.0, 0.0));
+    }
+
+    #[test]
+    fn overlapping_tiles_share_weight() {
+        let mut fb = Framebuffer::new(2, 1);
+        let filter = Filter {
+            kind: PixelFilter::Tent,
+            radius: 1.5,
+        };
+
+        let mut left = TileFilm::new(&Tile::new(0, 0, 1, 1), &filter, 2, 1);
+        left.add_sample(0.0, 0.0, &Radiance::new(1.0, 1.0, 1.0), &filter);
+        let mut right = TileFilm::new(&Tile::new(1, 0, 2, 1), &filter, 2, 1);
+        right.add_sample(1.0, 0.0, &Radiance::new(0.0, 0.0, 0.0), &filter);
+
+        fb.merge_tile(left);
+        fb.merge_tile(right);
+
+        // Both pixels see both samples, the nearer one weighs more
+        let near = 1.5 * 1.5;
+        let far = 0.5 * 1.5;
+        let expected = near / (near + far);
+        assert!((fb.get_pixel(0, 0).r() - expected).abs() < 1e-12);
+        assert!((fb.get_pixel(1, 0).r() - (1.0 - expected)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn negative_lobe_at_a_corner() {
+        let mut fb = Framebuffer::new(3, 3);
+        let filter = Filter {
+            kind: PixelFilter::Lanczos,
+            radius: 2.0,
+        };
+
+        // The corner pixel is only reached by the negative lobe
+        let mut film = TileFilm::new(&Tile::new(0, 0, 3, 3), &filter, 3, 3);
+        film.add_sample(1.4, 0.0, &Radiance::new(0.5, 0.5, 0.5), &filter);
+        assert!(film.weights[0] < 0.0);
+
+        fb.merge_tile(film);
+
+        assert_eq!(fb.get_pixel(0, 0), &Radiance::black());
+        assert!((fb.get_pixel(1, 0).r() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds_pixel() {
+        let fb = Framebuffer::new(4, 3);
+        let _ = fb.get_pixel(4, 0);
+    }
+}
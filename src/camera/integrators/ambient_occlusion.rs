@@ -0,0 +1,52 @@
+use crate::{
+    camera::{Ray, RenderWorld, Sampler, integrators::Integrator},
+    utils::{Interval, Radiance, Vec3},
+};
+
+/// How open the sky is above the first hit. A ray leaves in a random
+/// direction around the normal, the sample is white if it gets
+/// further than distance and black if it runs into something. Rays
+/// that miss everything are white.
+#[derive(Debug, Clone)]
+pub struct AmbientOcclusion {
+    pub(crate) distance: f64,
+}
+
+impl AmbientOcclusion {
+    /// # Panics
+    /// Panics if the distance is not positive.
+    pub fn new(distance: f64) -> AmbientOcclusion {
+        assert!(
+            distance > 0.0,
+            "The occlusion distance must be positive. You passed {distance}"
+        );
+
+        AmbientOcclusion { distance }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, r: Ray, world: &RenderWorld, sampler: &mut Sampler) -> Radiance {
+        let r = r.with_medium_sample(sampler.medium());
+        let Some(h) = world.objects.hit(&r, &Interval::new(0.001, f64::INFINITY)) else {
+            return Radiance::new(1.0, 1.0, 1.0);
+        };
+
+        // Cosine weighted like a Lambertian bounce
+        sampler.start_bounce();
+        let mut direction = h.normal() + Vec3::unit_vector_from_sample(sampler.get_2d());
+        if direction.near_zero() {
+            direction = h.normal();
+        }
+
+        let probe = Ray::new_at_time(h.position(), direction.unit_vector(), r.time())
+            .with_medium_sample(sampler.medium());
+        match world
+            .objects
+            .hit(&probe, &Interval::new(0.001, self.distance))
+        {
+            Some(_) => Radiance::black(),
+            None => Radiance::new(1.0, 1.0, 1.0),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    camera::{Ray, RenderWorld, Sampler, integrators::Integrator},
+    materials::Materials,
+    objects::HitRecord,
+    utils::{Interval, Radiance},
+};
+
+/// The first thing a camera ray hits, debug views only look at that
+fn first_hit(r: &Ray, world: &RenderWorld) -> Option<HitRecord> {
+    world.objects.hit(r, &Interval::new(0.001, f64::INFINITY))
+}
+
+/// See `Integrators::Normals`
+#[derive(Debug, Clone)]
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn radiance(&self, r: Ray, world: &RenderWorld, _sampler: &mut Sampler) -> Radiance {
+        let Some(h) = first_hit(&r, world) else {
+            return Radiance::black();
+        };
+
+        let n = h.normal();
+        Radiance::new(n.x() + 1.0, n.y() + 1.0, n.z() + 1.0) / 2.0
+    }
+}
+
+/// See `Integrators::Uv`
+#[derive(Debug, Clone)]
+pub struct Uv;
+
+impl Integrator for Uv {
+    fn radiance(&self, r: Ray, world: &RenderWorld, _sampler: &mut Sampler) -> Radiance {
+        let Some(h) = first_hit(&r, world) else {
+            return Radiance::black();
+        };
+
+        Radiance::new(h.u_texture, h.v_texture, 0.0)
+    }
+}
+
+/// How far the first hit is from the camera, black up close and
+/// white at far or further. Rays that miss everything are white.
+#[derive(Debug, Clone)]
+pub struct DepthView {
+    pub(crate) far: f64,
+}
+
+impl DepthView {
+    /// # Panics
+    /// Panics if far is not positive.
+    pub fn new(far: f64) -> DepthView {
+        assert!(
+            far > 0.0,
+            "The far distance must be positive. You passed {far}"
+        );
+
+        DepthView { far }
+    }
+}
+
+impl Integrator for DepthView {
+    fn radiance(&self, r: Ray, world: &RenderWorld, _sampler: &mut Sampler) -> Radiance {
+        let depth = match first_hit(&r, world) {
+            Some(h) => (h.t() * r.direction().length() / self.far).min(1.0),
+            None => 1.0,
+        };
+
+        Radiance::new(depth, depth, depth)
+    }
+}
+
+/// How many bounding boxes a camera ray is tested against before the
+/// first hit is found. No boxes is blue, half of max_boxes is green
+/// and max_boxes or more is red.
+#[derive(Debug, Clone)]
+pub struct BvhHeatmap {
+    pub(crate) max_boxes: u32,
+}
+
+impl BvhHeatmap {
+    /// # Panics
+    /// Panics if max_boxes is 0.
+    pub fn new(max_boxes: u32) -> BvhHeatmap {
+        assert!(max_boxes > 0, "The heatmap needs a max of at least one box");
+
+        BvhHeatmap { max_boxes }
+    }
+}
+
+impl Integrator for BvhHeatmap {
+    fn radiance(&self, r: Ray, world: &RenderWorld, _sampler: &mut Sampler) -> Radiance {
+        let mut boxes = 0;
+        world
+            .objects
+            .hit_counting(&r, &Interval::new(0.001, f64::INFINITY), &mut boxes);
+
+        let heat = (boxes as f64 / self.max_boxes as f64).min(1.0);
+        if heat < 0.5 {
+            Radiance::new(0.0, 2.0 * heat, 1.0 - 2.0 * heat)
+        } else {
+            Radiance::new(2.0 * heat - 1.0, 2.0 - 2.0 * heat, 0.0)
+        }
+    }
+}
+
+/// See `Integrators::MaterialId`
+#[derive(Debug, Clone)]
+pub struct MaterialId;
+
+impl Integrator for MaterialId {
+    fn radiance(&self, r: Ray, world: &RenderWorld, _sampler: &mut Sampler) -> Radiance {
+        let Some(h) = first_hit(&r, world) else {
+            return Radiance::black();
+        };
+
+        match h.material() {
+            Materials::Lambertian(_) => Radiance::new(0.9, 0.3, 0.3),
+            Materials::Metal(_) => Radiance::new(0.3, 0.9, 0.3),
+            Materials::Dielectric(_) => Radiance::new(0.3, 0.3, 0.9),
+            Materials::DiffuseLight(_) => Radiance::new(0.9, 0.9, 0.3),
+            Materials::HenyeyGreenstein(_) => Radiance::new(0.3, 0.9, 0.9),
+        }
+    }
+}
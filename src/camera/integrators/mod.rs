@@ -0,0 +1,216 @@
+use clap::{ValueEnum, builder::PossibleValue};
+
+use crate::{
+    camera::{
+        Ray, RenderWorld, Sampler,
+        integrators::{
+            ambient_occlusion::AmbientOcclusion,
+            debug::{BvhHeatmap, DepthView},
+            path::{DirectLighting, PathIntegrator},
+        },
+    },
+    utils::Radiance,
+};
+
+pub mod ambient_occlusion;
+pub mod debug;
+pub mod path;
+
+/// A wrapper for the ways the camera can turn a ray into light, this
+/// handles dispatching calls to individual integrators. The path
+/// integrator is the renderer, the others are views for lookdev and
+/// for finding bugs. Debug views are best saved with a linear
+/// transfer function so the values land in the image as they are.
+#[derive(Debug, Clone)]
+pub enum Integrators {
+    Path(PathIntegrator),
+    DirectLighting(DirectLighting),
+    AmbientOcclusion(AmbientOcclusion),
+    /// The normal at the first hit mapped from [-1, 1] to [0, 1]
+    Normals,
+    /// The texture coordinates at the first hit in red and green
+    Uv,
+    Depth(DepthView),
+    BvhHeatmap(BvhHeatmap),
+    /// Every kind of material in its own flat color
+    MaterialId,
+}
+
+impl Default for Integrators {
+    fn default() -> Self {
+        Integrators::Path(PathIntegrator::new(10, Some(3)))
+    }
+}
+
+impl Integrators {
+    /// Picks a view from a name such as "normals", this is meant for
+    /// command line flags. The path integrator is left out since the
+    /// scenes set it up themselves. Direct lighting takes the
+    /// camera's max depth once it is set on a camera, ambient
+    /// occlusion looks one unit away, depth is white 100 units away
+    /// and the heatmap is red at 64 boxes. Returns None if the name
+    /// is not a view.
+    pub fn from_name(name: &str) -> Option<Integrators> {
+        Integrators::from_str(name, true).ok()
+    }
+
+    /// The integrator and its settings as numbers for checkpoints,
+    /// integrators with the same key render the same image
+    pub(crate) fn key(&self) -> [u64; 3] {
+        match self {
+            Integrators::Path(p) => [
+                0,
+                p.max_depth as u64,
+                p.roulette_depth.map_or(u64::MAX, u64::from),
+            ],
+            Integrators::DirectLighting(d) => [1, d.max_depth as u64, 0],
+            Integrators::AmbientOcclusion(a) => [2, a.distance.to_bits(), 0],
+            Integrators::Normals => [3, 0, 0],
+            Integrators::Uv => [4, 0, 0],
+            Integrators::Depth(d) => [5, d.far.to_bits(), 0],
+            Integrators::BvhHeatmap(b) => [6, b.max_boxes as u64, 0],
+            Integrators::MaterialId => [7, 0, 0],
+        }
+    }
+
+    pub(crate) fn radiance(&self, r: Ray, world: &RenderWorld, sampler: &mut Sampler) -> Radiance {
+        match self {
+            Integrators::Path(p) => p.radiance(r, world, sampler),
+            Integrators::DirectLighting(d) => d.radiance(r, world, sampler),
+            Integrators::AmbientOcclusion(a) => a.radiance(r, world, sampler),
+            Integrators::Normals => debug::Normals.radiance(r, world, sampler),
+            Integrators::Uv => debug::Uv.radiance(r, world, sampler),
+            Integrators::Depth(d) => d.radiance(r, world, sampler),
+            Integrators::BvhHeatmap(b) => b.radiance(r, world, sampler),
+            Integrators::MaterialId => debug::MaterialId.radiance(r, world, sampler),
+        }
+    }
+}
+
+/// The command line names of the views, see from_name for their
+/// settings. The path integrator has none.
+impl ValueEnum for Integrators {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Integrators::DirectLighting(DirectLighting { max_depth: 10 }),
+            Integrators::AmbientOcclusion(AmbientOcclusion { distance: 1.0 }),
+            Integrators::Normals,
+            Integrators::Uv,
+            Integrators::Depth(DepthView { far: 100.0 }),
+            Integrators::BvhHeatmap(BvhHeatmap { max_boxes: 64 }),
+            Integrators::MaterialId,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let value = match self {
+            Integrators::Path(_) => return None,
+            Integrators::DirectLighting(_) => {
+                PossibleValue::new("direct").help("Direct lighting only")
+            }
+            Integrators::AmbientOcclusion(_) => PossibleValue::new("ao").help("Ambient occlusion"),
+            Integrators::Normals => PossibleValue::new("normals").help("Normals at the first hit"),
+            Integrators::Uv => {
+                PossibleValue::new("uv").help("Texture coordinates at the first hit")
+            }
+            Integrators::Depth(_) => PossibleValue::new("depth").help("Distance to the first hit"),
+            Integrators::BvhHeatmap(_) => {
+                PossibleValue::new("heatmap").help("How many bounding boxes a ray tests")
+            }
+            Integrators::MaterialId => {
+                PossibleValue::new("material").help("A flat color per kind of material")
+            }
+        };
+
+        Some(value)
+    }
+}
+
+/// An integrator must implement this to render. The camera calls
+/// radiance once for every sample with the ray it casts, random
+/// choices should come from the sampler.
+pub trait Integrator {
+    fn radiance(&self, r: Ray, world: &RenderWorld, sampler: &mut Sampler) -> Radiance;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        camera::SamplingMethod,
+        lights::LightList,
+        materials::{Materials, lambertian::Lambertian},
+        objects::{Hittables, bvhwrapper::BVHWrapper, hitlist::HitList, sphere::Sphere},
+        scene::Skybox,
+        utils::{Color, Point3, Vec3},
+    };
+
+    fn gray() -> Materials {
+        Materials::Lambertian(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5), 1.0))
+    }
+
+    fn world(objects: Vec<Hittables>) -> RenderWorld {
+        let objects = BVHWrapper::new_wrapper(HitList::new(objects));
+        RenderWorld::new(Arc::new(objects), LightList::default(), Skybox::Default)
+    }
+
+    #[test]
+    fn debug_views_see_the_first_hit() {
+        let world = world(
+            (0..4)
+                .map(|k| Sphere::new(Point3::new(5.0 * k as f64, 0.0, 0.0), 1.0, gray()))
+                .map(Hittables::Sphere)
+                .collect(),
+        );
+        let mut sampler = Sampler::new(SamplingMethod::Square, 1, 0);
+        sampler.start_pixel_sample(0, 0, 0);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let missed = Ray::new(Point3::new(0.0, 3.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+
+        let mut view =
+            |integrator: Integrators, r: &Ray| integrator.radiance(r.clone(), &world, &mut sampler);
+
+        assert_eq!(
+            view(Integrators::Normals, &ray),
+            Radiance::new(0.5, 0.5, 1.0)
+        );
+        let depth = Integrators::Depth(DepthView::new(4.0));
+        assert_eq!(view(depth.clone(), &ray), Radiance::new(0.5, 0.5, 0.5));
+        assert_eq!(view(depth, &missed), Radiance::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            view(Integrators::MaterialId, &ray),
+            Radiance::new(0.9, 0.3, 0.3)
+        );
+
+        // Missing the root box is one test, a hit goes down the tree
+        let heatmap = Integrators::BvhHeatmap(BvhHeatmap::new(2));
+        assert_eq!(view(heatmap.clone(), &missed), Radiance::new(0.0, 1.0, 0.0));
+        assert_eq!(view(heatmap, &ray), Radiance::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn ambient_occlusion_sees_cover() {
+        let floor = Hittables::Sphere(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, gray()));
+        let roof = Hittables::Sphere(Sphere::new(Point3::new(0.0, 1.0, 0.0), 0.5, gray()));
+        let ray = Ray::new(Point3::new(0.0, 0.2, 3.0), Vec3::new(0.0, -0.2, -3.0));
+
+        let occlusion = |objects: Vec<Hittables>| {
+            let world = world(objects);
+            let ao = Integrators::AmbientOcclusion(AmbientOcclusion::new(2.0));
+            let mut sampler = Sampler::new(SamplingMethod::Square, 256, 0);
+            let total: f64 = (0..256)
+                .map(|n| {
+                    sampler.start_pixel_sample(0, 0, n);
+                    ao.radiance(ray.clone(), &world, &mut sampler).r()
+                })
+                .sum();
+            total / 256.0
+        };
+
+        assert_eq!(occlusion(vec![floor.clone()]), 1.0);
+        let covered = occlusion(vec![floor, roof]);
+        assert!(covered > 0.5 && covered < 0.95);
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{
+    camera::{Ray, RenderWorld, Sampler, integrators::Integrator},
+    objects::HitRecord,
+    utils::{Color, Interval, Radiance},
+};
+
+/// The path tracer. At every bounce off of a rough surface one light
+/// is also sampled directly, the two ways of finding a light are
+/// weighted against each other with the power heuristic. After
+/// roulette_depth bounces the path ends at random, see
+/// `Camera::set_roulette_depth`.
+#[derive(Debug, Clone)]
+pub struct PathIntegrator {
+    pub(crate) max_depth: u32,
+    pub(crate) roulette_depth: Option<u32>,
+}
+
+impl PathIntegrator {
+    pub fn new(max_depth: u32, roulette_depth: Option<u32>) -> PathIntegrator {
+        PathIntegrator {
+            max_depth,
+            roulette_depth,
+        }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn radiance(&self, r: Ray, world: &RenderWorld, sampler: &mut Sampler) -> Radiance {
+        let mut r = r;
+        let mut color = Radiance::black();
+        // How much of the light found from here on reaches the camera
+        let mut throughput = Radiance::new(1.0, 1.0, 1.0);
+        // The density the last bounce picked r with. It is None for camera
+        // rays and mirror-like bounces, lights are not sampled for those
+        // so whatever r hits counts in full.
+        let mut bsdf_pdf = None;
+
+        for bounce in 0..self.max_depth {
+            r = r.with_medium_sample(sampler.medium());
+            // The interval starts at 0.001 to fix the 'shadow acne' behavior
+            let hit = world.objects.hit(&r, &Interval::new(0.001, f64::INFINITY));
+
+            let Some(h) = hit else {
+                let sky = world.skybox.radiance(r.direction());
+                color += throughput * sky * bounce_weight(bsdf_pdf, world, &r, f64::INFINITY);
+                break;
+            };
+
+            let material = h.material();
+            let emitted = material.emitted(&r, &h);
+            if emitted != Radiance::black() {
+                let weight = bounce_weight(bsdf_pdf, world, &r, h.t());
+                color += throughput.clone() * emitted * weight;
+            }
+
+            let mut attenuation = Color::black();
+            sampler.start_bounce();
+            let scatter = material.scatter(&r, &h, &mut attenuation, sampler);
+            // The light found this way stands in for the next bounce
+            if bounce + 1 < self.max_depth {
+                color += throughput.clone() * direct_light(&r, &h, world, sampler, true);
+            }
+
+            let Some(s) = scatter else {
+                break;
+            };
+            bsdf_pdf = material.eval(&r, &h, s.direction()).map(|(_, pdf)| pdf);
+            throughput = attenuation * throughput;
+
+            // Dim paths are likely to end, the ones that survive carry
+            // the light of the ones that did not
+            if let Some(min_depth) = self.roulette_depth
+                && bounce + 1 >= min_depth
+            {
+                let survive = throughput.max_channel().min(0.95);
+                if sampler.roulette() >= survive {
+                    break;
+                }
+                throughput = throughput / survive;
+            }
+
+            r = s;
+        }
+
+        color
+    }
+}
+
+/// Only the light that reaches a surface straight from a light or
+/// the sky, without bouncing off of anything else first. Mirrors and
+/// glass are followed up to max_depth times to the rough surface
+/// behind them.
+#[derive(Debug, Clone)]
+pub struct DirectLighting {
+    pub(crate) max_depth: u32,
+}
+
+impl DirectLighting {
+    pub fn new(max_depth: u32) -> DirectLighting {
+        DirectLighting { max_depth }
+    }
+}
+
+impl Integrator for DirectLighting {
+    fn radiance(&self, r: Ray, world: &RenderWorld, sampler: &mut Sampler) -> Radiance {
+        let mut r = r;
+        let mut color = Radiance::black();
+        let mut throughput = Radiance::new(1.0, 1.0, 1.0);
+
+        for _ in 0..self.max_depth {
+            r = r.with_medium_sample(sampler.medium());
+            let Some(h) = world.objects.hit(&r, &Interval::new(0.001, f64::INFINITY)) else {
+                color += throughput * world.skybox.radiance(r.direction());
+                break;
+            };
+
+            let material = h.material();
+            color += throughput.clone() * material.emitted(&r, &h);
+
+            let mut attenuation = Color::black();
+            sampler.start_bounce();
+            let scatter = material.scatter(&r, &h, &mut attenuation, sampler);
+            color += throughput.clone() * direct_light(&r, &h, world, sampler, false);
+
+            // Rough surfaces end the path, lights were sampled for them
+            match scatter {
+                Some(s) if material.eval(&r, &h, s.direction()).is_none() => {
+                    throughput = attenuation * throughput;
+                    r = s;
+                }
+                _ => break,
+            }
+        }
+
+        color
+    }
+}
+
+/// Samples one light from the hit at rec, shadow rays check how much
+/// of its light gets past whatever is in the way. With mis the sample is weighted against
+/// the bounce that could have found the light too.
+fn direct_light(
+    r_in: &Ray,
+    rec: &HitRecord,
+    world: &RenderWorld,
+    sampler: &mut Sampler,
+    mis: bool,
+) -> Radiance {
+    if world.lights.is_empty() {
+        return Radiance::black();
+    }
+
+    sampler.start_light();
+    let u_light = sampler.get_1d();
+    let u = sampler.get_2d();
+
+    let material = rec.material();
+    let Some(sample) = world
+        .lights
+        .sample(&rec.position(), r_in.time(), u_light, u)
+    else {
+        return Radiance::black();
+    };
+    let Some((f, bsdf_pdf)) = material.eval(r_in, rec, &sample.direction) else {
+        return Radiance::black();
+    };
+
+    if f == Radiance::black() {
+        return Radiance::black();
+    }
+    // Media in the way dim the light instead of blocking it
+    let shadow = Ray::new_at_time(rec.position(), sample.direction.clone(), r_in.time())
+        .with_medium_sample(sampler.shadow());
+    let between = Interval::new(0.001, sample.distance - 0.001);
+    let transmittance = world.objects.transmittance(&shadow, &between);
+    if transmittance <= 0.0 {
+        return Radiance::black();
+    }
+
+    let weight = if sample.delta || !mis {
+        1.0
+    } else {
+        power_heuristic(sample.pdf, bsdf_pdf)
+    };
+    f * sample.radiance * (transmittance * weight / sample.pdf)
+}
+
+/// How much of the light a bounce runs into is kept. Light sampling
+/// could have found it too, so it takes its share of the weight.
+fn bounce_weight(bsdf_pdf: Option<f64>, world: &RenderWorld, r: &Ray, t: f64) -> f64 {
+    match bsdf_pdf {
+        Some(pdf) => power_heuristic(pdf, world.lights.pdf(r, t)),
+        None => 1.0,
+    }
+}
+
+/// The weight of a sample taken with density a when it could also
+/// have been taken with density b
+fn power_heuristic(a: f64, b: f64) -> f64 {
+    if a <= 0.0 {
+        return 0.0;
+    }
+
+    (a * a) / (a * a + b * b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        camera::SamplingMethod,
+        lights::LightList,
+        materials::{Materials, diffuse_light::DiffuseLight, lambertian::Lambertian},
+        objects::{Hittables, hitlist::HitList, sphere::Sphere},
+        scene::Skybox,
+        utils::Point3,
+    };
+
+    #[test]
+    fn lights_add_their_emission() {
+        let light = Materials::DiffuseLight(DiffuseLight::new_from_color(
+            Color::new(1.0, 0.5, 0.25),
+            4.0,
+        ));
+        let objects = vec![Hittables::Sphere(Sphere::new(Point3::origin(), 1.0, light))];
+        let sky = Skybox::Solid(Radiance::black());
+        let lights = LightList::new(&objects, &[], &sky);
+        let world = RenderWorld::new(Arc::new(objects[0].clone()), lights, sky);
+        let mut sampler = Sampler::new(SamplingMethod::Square, 1, 0);
+        sampler.start_pixel_sample(0, 0, 0);
+
+        // Lights glow against a black sky and reflect nothing
+        let ray = Ray::new(Point3::new(0.0, 0.0, 3.0), Point3::new(0.0, 0.0, -1.0));
+        let seen = PathIntegrator::new(5, None).radiance(ray.clone(), &world, &mut sampler);
+        assert_eq!(seen, Radiance::new(4.0, 2.0, 1.0));
+
+        let missed = Ray::new(Point3::new(0.0, 3.0, 3.0), Point3::new(0.0, 0.0, -1.0));
+        let seen = PathIntegrator::new(5, None).radiance(missed, &world, &mut sampler);
+        assert_eq!(seen, Radiance::black());
+    }
+
+    #[test]
+    fn light_sampling_matches_bounces() {
+        // A small light over a gray floor in the dark
+        let light = Materials::DiffuseLight(DiffuseLight::new_from_color(Color::white(), 8.0));
+        let gray =
+            Materials::Lambertian(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5), 1.0));
+        let objects = vec![
+            Hittables::Sphere(Sphere::new(Point3::new(0.0, 2.0, 0.0), 0.25, light)),
+            Hittables::Sphere(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, gray)),
+        ];
+        let objects_hit = Arc::new(Hittables::HitList(HitList::new(objects.clone())));
+        let sky = Skybox::Solid(Radiance::black());
+
+        let with_lights = LightList::new(&objects, &[], &sky);
+        let worlds = [
+            RenderWorld::new(objects_hit.clone(), LightList::default(), sky.clone()),
+            RenderWorld::new(objects_hit, with_lights, sky),
+        ];
+
+        // The mean and variance of the floor's brightness
+        let samples = 50_000;
+        let [(bounces, bounce_var), (sampled, sampled_var)] = worlds.map(|world| {
+            let mut sampler = Sampler::new(SamplingMethod::Square, samples, 7);
+            let (mut sum, mut sum_sq) = (0.0, 0.0);
+            for n in 0..samples {
+                sampler.start_pixel_sample(0, 0, n);
+                let ray = Ray::new(Point3::new(0.0, 1.0, 3.0), Point3::new(0.0, -1.0, -3.0));
+                let seen = PathIntegrator::new(2, None)
+                    .radiance(ray, &world, &mut sampler)
+                    .r();
+                sum += seen;
+                sum_sq += seen * seen;
+            }
+            let mean = sum / samples as f64;
+
+            (mean, sum_sq / samples as f64 - mean * mean)
+        });
+
+        assert!((bounces - sampled).abs() < 0.08 * bounces);
+        assert!(sampled_var * 10.0 < bounce_var);
+    }
+
+    #[test]
+    fn roulette_keeps_the_mean() {
+        // A small light in a closed gray room, light bounces around a lot
+        let light = Materials::DiffuseLight(DiffuseLight::new_from_color(Color::white(), 8.0));
+        let gray =
+            Materials::Lambertian(Lambertian::new_from_color(Color::new(0.6, 0.6, 0.6), 1.0));
+        let objects = vec![
+            Hittables::Sphere(Sphere::new(Point3::new(0.0, 2.0, 0.0), 0.5, light)),
+            Hittables::Sphere(Sphere::new(Point3::origin(), 4.0, gray)),
+        ];
+        let sky = Skybox::Solid(Radiance::black());
+        let lights = LightList::new(&objects, &[], &sky);
+        let world = RenderWorld::new(
+            Arc::new(Hittables::HitList(HitList::new(objects))),
+            lights,
+            sky,
+        );
+
+        let samples = 4_000;
+        let [full, roulette] = [None, Some(2)].map(|roulette_depth| {
+            let mut sampler = Sampler::new(SamplingMethod::Square, samples, 3);
+            let mut sum = 0.0;
+            for n in 0..samples {
+                sampler.start_pixel_sample(0, 0, n);
+                let ray = Ray::new(Point3::new(0.0, -1.0, 0.0), Point3::new(0.3, -1.0, 0.2));
+                sum += PathIntegrator::new(12, roulette_depth)
+                    .radiance(ray, &world, &mut sampler)
+                    .r();
+            }
+            sum / samples as f64
+        });
+
+        assert!((full - roulette).abs() < 0.05 * full);
+    }
+}
@@ -1,31 +1,74 @@
 use std::{
-    fs::OpenOptions,
-    io::{BufWriter, Error, Write},
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
-use dashmap::DashMap;
 use indicatif::{MultiProgress, ProgressStyle};
-use rand::Rng;
 
 use crate::{
-    camera::cpu_threading::ThreadInfo,
+    camera::{
+        adaptive::AdaptiveSampling,
+        aperture::Aperture,
+        checkpoint::RenderKey,
+        cpu_threading::ThreadInfo,
+        crop::CropWindow,
+        filter::Filter,
+        physical::{PhysicalLens, exposure_stops},
+        stereo::{Eye, Stereo},
+        tiles::Tile,
+    },
+    lights::LightList,
     objects::Hittables,
     scene::Skybox,
-    timeline::TransformTimeline,
-    utils::{Color, Degrees, Point3, Radians, Vec3},
+    timeline::{InterpolationType, ScalarTimeline, TransformTimeline},
+    utils::{Degrees, Interval, Point3, Radians, Vec3},
 };
 
+mod adaptive;
+mod aperture;
+mod checkpoint;
 mod cpu_threading;
+mod crop;
+mod filter;
+mod framebuffer;
+mod integrators;
 mod miscellaneous;
+mod output_format;
+mod physical;
+mod projection;
 mod ray_casting;
 mod rendering_compute;
-
+mod sampler;
+mod stereo;
+mod tiles;
+mod tone_mapping;
+
+pub use aperture::{ApertureMask, ApertureShape};
+pub(crate) use checkpoint::checkpoint_path;
+pub use crop::CropOutput;
+pub use filter::PixelFilter;
+pub use framebuffer::Framebuffer;
+pub use integrators::{
+    Integrators,
+    ambient_occlusion::AmbientOcclusion,
+    debug::{BvhHeatmap, DepthView},
+    path::{DirectLighting, PathIntegrator},
+};
+pub use output_format::OutputFormat;
+pub use physical::SensorSize;
+pub use projection::Projection;
 pub use ray_casting::Ray;
+pub(crate) use ray_casting::RenderWorld;
+pub(crate) use sampler::Sampler;
+pub use sampler::SamplingMethod;
+pub use stereo::StereoLayout;
+pub use tiles::TileOrder;
+pub use tone_mapping::{ToneMapper, ToneMapping, TransferFunction};
 
 struct Viewport {
-    viewport_height: f64,
-    viewport_width: f64,
     image_height: u32,
     image_width: u32,
 }
@@ -38,9 +81,6 @@ impl Viewport {
         let image_height = image_height.clamp(1, u32::MAX);
 
         Viewport {
-            // These will be initialized by the camera
-            viewport_height: 0.0,
-            viewport_width: 0.0,
             image_height,
             image_width,
         }
@@ -50,42 +90,70 @@ impl Viewport {
 impl Clone for Viewport {
     fn clone(&self) -> Self {
         Viewport {
-            viewport_height: self.viewport_height,
-            viewport_width: self.viewport_width,
             image_height: self.image_height,
             image_width: self.image_width,
         }
     }
 }
 
-#[derive(Clone)]
-pub enum SamplingMethod {
-    Square,
+/// How the camera leans into turns, see `Camera::set_banking`
+#[derive(Debug, Clone)]
+struct Banking {
+    strength: f64,
+    smoothing: f64,
 }
 
 pub struct Camera {
     // camera position
     viewport: Viewport,
-    vfov: Radians,
+    /// In degrees
+    vfov: ScalarTimeline,
     aspect_ratio: f64,
+    projection: Projection,
 
     // look dir
     pub look_from: TransformTimeline,
     pub look_at: TransformTimeline,
-    vup: Vec3,
-
-    // defocus fields
-    defocus_angle: Radians,
-    focus_dist: f64,
+    /// The x, y and z of the up vector
+    vup: [ScalarTimeline; 3],
+    /// Turns the camera around the view direction, in degrees
+    roll: ScalarTimeline,
+    bank: Option<Banking>,
+
+    // defocus fields, the angle is in degrees
+    defocus_angle: ScalarTimeline,
+    focus_dist: ScalarTimeline,
+    lens: Option<PhysicalLens>,
+    iso: Option<f64>,
+    aperture: Aperture,
+
+    // stereo, eye is the one being rendered
+    stereo: Option<Stereo>,
+    eye: Option<Eye>,
 
     // sampling
     samples: u32,
+    adaptive: Option<AdaptiveSampling>,
     sampling_method: SamplingMethod,
+    filter: Filter,
+    integrator: Integrators,
     max_depth: u32,
+    roulette_depth: Option<u32>,
+    seed: u64,
 
     // threads
     thread_count: usize,
-    results: Arc<DashMap<(u32, u32), Color>>,
+    tile_size: u32,
+    tile_order: TileOrder,
+    crop: Option<CropWindow>,
+
+    // Output of the last render
+    framebuffer: Framebuffer,
+    tone_mapping: ToneMapping,
+
+    // Saving and resuming partial renders
+    checkpoint_interval: Option<Duration>,
+    resume: bool,
 
     // progress bars
     mp: MultiProgress,
@@ -111,42 +179,62 @@ impl Camera {
         thread_count: usize,
     ) -> Camera {
         // Location and viewport config
-        let fov = Degrees::new(90.0).as_radians();
-        let h = (fov.get_angle() / 2.0).tan();
-        let mut v = Viewport::new(aspect_ratio, image_width);
-
-        // calc default focal length
-        let focal_length = (Point3::new(0.0, 0.0, 0.0) - Point3::new(0.0, 0.0, -1.0)).length();
-
-        v.viewport_height = 2.0 * h * focal_length;
-        v.viewport_width = v.viewport_height * (v.image_width as f64 / v.image_height as f64);
+        let v = Viewport::new(aspect_ratio, image_width);
 
         // Sampling presets
         let samples = 10;
         let sampling_method = SamplingMethod::Square;
-        let max_depth = 10;
 
-        let results = Arc::new(DashMap::new());
+        let framebuffer = Framebuffer::new(v.image_width, v.image_height);
         let (mp, sty) = miscellaneous::init_pb();
 
         Camera {
             viewport: v,
-            vfov: fov,
+            vfov: ScalarTimeline::new(90.0),
             aspect_ratio,
+            projection: Projection::Perspective,
 
             look_from: TransformTimeline::new(Point3::origin(), Point3::origin(), 1.0),
             look_at: TransformTimeline::new(Point3::origin(), Point3::origin(), 1.0),
-            vup: Vec3::new(0.0, 1.0, 0.0),
-
-            defocus_angle: Radians::new_from_degrees(0.0),
-            focus_dist: 10.0,
+            vup: [
+                ScalarTimeline::new(0.0),
+                ScalarTimeline::new(1.0),
+                ScalarTimeline::new(0.0),
+            ],
+            roll: ScalarTimeline::new(0.0),
+            bank: None,
+
+            defocus_angle: ScalarTimeline::new(0.0),
+            focus_dist: ScalarTimeline::new(10.0),
+            lens: None,
+            iso: None,
+            aperture: Aperture::default(),
+
+            stereo: None,
+            eye: None,
 
             samples,
+            adaptive: None,
             sampling_method,
-            max_depth,
+            filter: Filter {
+                kind: PixelFilter::Box,
+                radius: 0.5,
+            },
+            integrator: Integrators::default(),
+            max_depth: 10,
+            roulette_depth: Some(3),
+            seed: 0,
 
             thread_count,
-            results,
+            tile_size: 32,
+            tile_order: TileOrder::Hilbert,
+            crop: None,
+
+            framebuffer,
+            tone_mapping: ToneMapping::default(),
+
+            checkpoint_interval: None,
+            resume: false,
 
             mp,
             sty,
@@ -157,16 +245,56 @@ impl Camera {
         }
     }
 
+    /// A fresh camera with the same image size, frame rate, shutter
+    /// angle and thread count, on the same frame. The lens and look
+    /// direction are the defaults of `Camera::new`.
+    pub fn new_matching(&self) -> Camera {
+        let mut cam = Camera::new(
+            self.aspect_ratio,
+            self.viewport.image_width,
+            self.frame_rate,
+            self.shutter_angle,
+            self.thread_count,
+        );
+        cam.frame = self.frame;
+
+        cam
+    }
+
     pub fn next_frame(&mut self) {
         self.frame += 1
     }
 
-    /// Returns a tuple of (width, height)
+    /// Returns a tuple of (width, height) of the images the camera
+    /// saves. A cropped window is only as big as the window, side by
+    /// side stereo is twice as wide and over under twice as tall.
     pub fn get_res(&self) -> (usize, usize) {
-        (
-            self.viewport.image_width as usize,
-            self.viewport.image_width as usize,
-        )
+        let (mut width, mut height) = match &self.crop {
+            Some(CropWindow {
+                region,
+                output: CropOutput::Cropped,
+            }) => (region.x1 - region.x0, region.y1 - region.y0),
+            _ => (self.viewport.image_width, self.viewport.image_height),
+        };
+
+        match self.stereo.as_ref().map(|stereo| &stereo.layout) {
+            Some(StereoLayout::SideBySide) => width *= 2,
+            Some(StereoLayout::OverUnder) => height *= 2,
+            _ => {}
+        }
+
+        (width as usize, height as usize)
+    }
+
+    /// Returns the times in seconds that the shutter is open for
+    /// the current frame. Rays are only cast inside of this window.
+    pub fn shutter_interval(&self) -> Interval {
+        // Convert to seconds
+        let current_time = (self.frame as f64) * (1.0 / self.frame_rate);
+        // Compute the shutter length from the shutter angle
+        let shutter_length = (self.shutter_angle / 360.0) * (1.0 / self.frame_rate);
+
+        Interval::new(current_time, current_time + shutter_length)
     }
 
     /// Returns a point representing where the camera originates rays from at its current frame
@@ -189,32 +317,77 @@ impl Camera {
         // for now neither of these are implemented on cameras so this is maybe ok, but probably needs to
         // be fixed
         self.look_from = TransformTimeline::new(loc, Point3::origin(), 1.0);
-
-        // we have to fix the viewport
-        self.fix_viewport();
     }
 
     /// Sets where the camera looks
     pub fn look_at(&mut self, loc: Point3) {
         self.look_at = TransformTimeline::new(loc, Point3::origin(), 1.0);
-
-        // we have to fix the viewport
-        self.fix_viewport();
     }
 
+    /// Sets which way is up for the camera, this replaces any vup keyframes
     pub fn set_vup(&mut self, vup: Vec3) {
-        self.vup = vup;
+        self.vup = [
+            ScalarTimeline::new(vup.x()),
+            ScalarTimeline::new(vup.y()),
+            ScalarTimeline::new(vup.z()),
+        ];
+    }
+
+    /// Adds an up vector keyframe. The vector is interpolated one
+    /// axis at a time so it should not pass through the view direction.
+    pub fn add_vup_keyframe(&mut self, vup: Vec3, keyframe: f64, it: InterpolationType) {
+        for (timeline, value) in self.vup.iter_mut().zip([vup.x(), vup.y(), vup.z()]) {
+            timeline.add_keyframe(value, keyframe, it.clone());
+        }
+    }
+
+    /// Rolls the camera counterclockwise around the direction it looks
+    /// in, in degrees. This is the dutch angle and it replaces any roll
+    /// keyframes.
+    pub fn set_roll(&mut self, roll_degrees: f64) {
+        self.roll = ScalarTimeline::new(roll_degrees);
+    }
+
+    /// Adds a roll keyframe in degrees
+    pub fn add_roll_keyframe(&mut self, roll_degrees: f64, keyframe: f64, it: InterpolationType) {
+        self.roll.add_keyframe(roll_degrees, keyframe, it);
+    }
+
+    /// Banks the camera into turns like a plane, the roll comes from
+    /// how hard look_from curves sideways. With the world in meters a
+    /// strength of 1.0 is a real coordinated turn. smoothing is how
+    /// many seconds of the path around each moment are looked at,
+    /// this softens the sharp corners of LERP keyframes. A strength
+    /// of 0.0 turns banking off. This is added to the roll.
+    ///
+    /// # Panics:
+    /// This panics if strength is negative or smoothing is not positive.
+    pub fn set_banking(&mut self, strength: f64, smoothing: f64) {
+        assert!(
+            strength >= 0.0,
+            "The banking strength cannot be negative. {strength} is invalid."
+        );
+        assert!(
+            smoothing > 0.0,
+            "The banking smoothing must be positive. {smoothing} is invalid."
+        );
+
+        self.bank = (strength > 0.0).then_some(Banking {
+            strength,
+            smoothing,
+        });
     }
 
     /// Sets the vertical FOV, takes degrees and changes
-    /// it automatically internally
+    /// it automatically internally. This replaces any vfov keyframes
+    /// and any lens set with set_focal_length, along with its f-number.
     pub fn set_vfov(&mut self, vfov_degrees: f64) {
-        self.vfov = Radians::new_from_degrees(vfov_degrees);
-
-        self.fix_viewport();
+        self.vfov = ScalarTimeline::new(vfov_degrees);
+        self.lens = None;
     }
 
-    /// Sets the FOV using the horizontal number
+    /// Sets the FOV using the horizontal number, like set_vfov this
+    /// replaces the lens
     pub fn set_hfov(&mut self, hfov_degrees: f64) {
         let hfov = Radians::new_from_degrees(hfov_degrees);
 
@@ -225,8 +398,61 @@ impl Camera {
         self.set_vfov(vfov.get_angle());
     }
 
+    /// Adds a vfov keyframe in degrees, this is how zooms are animated.
+    /// The field of view can change while the shutter is open so the
+    /// motion blur of a zoom is correct.
+    pub fn add_vfov_keyframe(&mut self, vfov_degrees: f64, keyframe: f64, it: InterpolationType) {
+        self.vfov.add_keyframe(vfov_degrees, keyframe, it);
+    }
+
+    /// Adds a keyframe to the focus distance, for focus pulls
+    pub fn add_focus_dist_keyframe(&mut self, fd: f64, keyframe: f64, it: InterpolationType) {
+        self.focus_dist.add_keyframe(fd, keyframe, it);
+    }
+
+    /// Adds a defocus angle keyframe in degrees. Like set_defocus_angle
+    /// this replaces an aperture set with set_f_number.
+    pub fn add_defocus_angle_keyframe(
+        &mut self,
+        da_degree: f64,
+        keyframe: f64,
+        it: InterpolationType,
+    ) {
+        self.defocus_angle.add_keyframe(da_degree, keyframe, it);
+
+        if let Some(lens) = &mut self.lens {
+            lens.f_number = None;
+        }
+    }
+
+    /// Sets how pixels map to the directions the camera looks in.
+    /// The defocus blur only applies to the perspective and
+    /// orthographic projections.
+    ///
+    /// # Panics:
+    /// This panics if an orthographic scale is not positive or a
+    /// fisheye fov is not between 0 and 360 degrees.
+    pub fn set_projection(&mut self, projection: Projection) {
+        match projection {
+            Projection::Orthographic { scale } => assert!(
+                scale > 0.0,
+                "The orthographic scale must be positive. {scale} is invalid."
+            ),
+            Projection::FisheyeEquidistant { fov } | Projection::FisheyeEquisolid { fov } => {
+                assert!(
+                    fov > 0.0 && fov <= 360.0,
+                    "The fisheye fov must be between 0 and 360 degrees. {fov} is invalid."
+                )
+            }
+            Projection::Perspective | Projection::Equirectangular => {}
+        }
+
+        self.projection = projection;
+    }
+
     /// Sets the number of samples. This option can be
-    /// expensive so set to a high value with caution.
+    /// expensive so set to a high value with caution. This
+    /// turns adaptive sampling off.
     ///
     /// #Panics:
     /// This panics if s is not a positive integer.
@@ -237,24 +463,289 @@ impl Camera {
         );
 
         self.samples = s;
+        self.adaptive = None;
+    }
+
+    /// Turns on adaptive sampling. Every pixel takes at least
+    /// min_samples, then keeps sampling in batches of min_samples
+    /// until the 95% confidence interval of its brightness is within
+    /// noise_threshold of the brightness (0.01 is 1%) or it has taken
+    /// max_samples.
+    ///
+    /// # Panics:
+    /// This panics if min_samples is less than 2, max_samples is
+    /// less than min_samples or noise_threshold is not positive.
+    pub fn set_adaptive_samples(
+        &mut self,
+        min_samples: u32,
+        max_samples: u32,
+        noise_threshold: f64,
+    ) {
+        assert!(
+            min_samples >= 2,
+            "Adaptive sampling needs at least 2 samples to measure noise. {min_samples} is invalid."
+        );
+        assert!(
+            max_samples >= min_samples,
+            "The max samples ({max_samples}) cannot be lower than the min samples ({min_samples})."
+        );
+        assert!(
+            noise_threshold > 0.0,
+            "The noise threshold must be positive. {noise_threshold} is invalid."
+        );
+
+        self.adaptive = Some(AdaptiveSampling {
+            min_samples,
+            max_samples,
+            noise_threshold,
+        });
+    }
+
+    /// Sets how the numbers for each sample are picked. The low
+    /// discrepancy methods give less noise at the same sample count.
+    pub fn set_sampling_method(&mut self, method: SamplingMethod) {
+        self.sampling_method = method;
+    }
+
+    /// Sets the filter that spreads each sample over the pixels
+    /// around it. radius is in pixels, a box with a radius of 0.5
+    /// averages the samples in each pixel which is the default.
+    /// Wider filters reduce aliasing and flicker in movies.
+    ///
+    /// # Panics:
+    /// This panics if radius is below 0.5, since samples could then
+    /// miss every pixel.
+    pub fn set_pixel_filter(&mut self, filter: PixelFilter, radius: f64) {
+        assert!(
+            radius >= 0.5,
+            "The filter radius must be at least 0.5 pixels. {radius} is invalid."
+        );
+
+        self.filter = Filter {
+            kind: filter,
+            radius,
+        };
     }
 
-    /// Sets the number of how many recursive calls the renderer
-    /// will make when a ray bounces off a surface
+    /// Sets the number of how many times the path and direct lighting
+    /// integrators let a ray bounce off a surface, the other
+    /// integrators have no bounces. It starts at 10. The camera keeps
+    /// this for integrators that are set later.
     pub fn set_max_depth(&mut self, md: u32) {
         self.max_depth = md;
+        self.apply_depth();
+    }
+
+    /// Paths that have bounced depth times end at random from then
+    /// on, the dimmer the light they still carry the sooner. The ones
+    /// that go on carry more to make up for it so the image stays the
+    /// same on average, only noisier. This keeps a high max depth
+    /// cheap. None turns it off so every path runs to the max depth.
+    /// It starts at 3 bounces. Only the path integrator does this, the
+    /// camera keeps it for when the path integrator is set later.
+    pub fn set_roulette_depth(&mut self, depth: Option<u32>) {
+        self.roulette_depth = depth;
+        self.apply_depth();
+    }
+
+    /// Sets how the camera turns rays into light, the path integrator
+    /// renders the scene and the others are views for lookdev and
+    /// debugging. The integrator bounces as deep as the camera says,
+    /// see set_max_depth and set_roulette_depth, whatever depth it was
+    /// made with is replaced.
+    pub fn set_integrator(&mut self, integrator: Integrators) {
+        self.integrator = integrator;
+        self.apply_depth();
     }
 
-    /// Sets the cameras defocus angle, argument is in degrees
+    /// Hands the camera's depths to the integrator
+    fn apply_depth(&mut self) {
+        match &mut self.integrator {
+            Integrators::Path(p) => {
+                p.max_depth = self.max_depth;
+                p.roulette_depth = self.roulette_depth;
+            }
+            Integrators::DirectLighting(d) => d.max_depth = self.max_depth,
+            _ => {}
+        }
+    }
+
+    /// Sets the seed the random numbers of a render come from. The
+    /// same scene, seed and resolution always renders the same
+    /// image, no matter how many threads render it.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Sets the cameras defocus angle, argument is in degrees.
+    /// This replaces an aperture set with set_f_number and any
+    /// defocus angle keyframes.
     pub fn set_defocus_angle(&mut self, da_degree: f64) {
-        self.defocus_angle = Radians::new_from_degrees(da_degree);
+        self.defocus_angle = ScalarTimeline::new(da_degree);
+
+        if let Some(lens) = &mut self.lens {
+            lens.f_number = None;
+        }
     }
 
-    /// Sets the cameras focus distance
+    /// Sets the shape of the lens opening, which is the shape out of
+    /// focus highlights take. This needs a defocus angle or an
+    /// f-number to show.
+    ///
+    /// # Panics:
+    /// This panics if a polygon has fewer than 3 blades.
+    pub fn set_aperture_shape(&mut self, shape: ApertureShape) {
+        if let ApertureShape::Polygon { blades, .. } = shape {
+            assert!(
+                blades >= 3,
+                "An aperture needs at least 3 blades. {blades} is invalid."
+            );
+        }
+
+        self.aperture.shape = shape;
+    }
+
+    /// Squeezes the aperture horizontally like an anamorphic lens, a
+    /// squeeze of 2.0 makes out of focus highlights twice as tall as
+    /// they are wide. 1.0 is a spherical lens.
+    ///
+    /// # Panics:
+    /// This panics if squeeze is not positive.
+    pub fn set_anamorphic_squeeze(&mut self, squeeze: f64) {
+        assert!(
+            squeeze > 0.0,
+            "The anamorphic squeeze must be positive. {squeeze} is invalid."
+        );
+
+        self.aperture.squeeze = squeeze;
+    }
+
+    /// Lets the lens barrel cut off the aperture towards the edges of
+    /// the frame, which turns out of focus highlights into cat's eyes
+    /// and darkens the corners. 0.0 turns it off, at 1.0 the corners
+    /// only see through a narrow sliver of the lens.
+    ///
+    /// # Panics:
+    /// This panics if strength is not between 0 and 1.
+    pub fn set_cat_eye(&mut self, strength: f64) {
+        assert!(
+            (0.0..=1.0).contains(&strength),
+            "The cat's eye strength must be between 0 and 1. {strength} is invalid."
+        );
+
+        self.aperture.cat_eye = strength;
+    }
+
+    /// Sets the field of view from a real lens, focal_length is in mm.
+    /// The sensor width covers the width of the image. This is an
+    /// alternative to set_vfov and set_hfov, and it is needed before
+    /// set_f_number.
+    ///
+    /// # Panics:
+    /// This panics if focal_length or the sensor size is not positive.
+    pub fn set_focal_length(&mut self, focal_length: f64, sensor: SensorSize) {
+        let (width, height) = sensor.dimensions();
+        assert!(
+            focal_length > 0.0,
+            "The focal length must be positive. {focal_length} is invalid."
+        );
+        assert!(
+            width > 0.0 && height > 0.0,
+            "The sensor size must be positive. {width}x{height} is invalid."
+        );
+
+        let lens = PhysicalLens {
+            focal_length,
+            sensor,
+            f_number: None,
+        };
+        self.set_hfov(lens.hfov());
+        self.lens = Some(lens);
+    }
+
+    /// Sets the aperture as an f-number such as 2.8. The depth of
+    /// field then follows from the focal length and the focus
+    /// distance, with the world measured in meters. This replaces
+    /// the defocus angle.
+    ///
+    /// # Panics:
+    /// This panics if no focal length was set or f_number is not positive.
+    pub fn set_f_number(&mut self, f_number: f64) {
+        assert!(
+            f_number > 0.0,
+            "The f-number must be positive. {f_number} is invalid."
+        );
+        let lens = self
+            .lens
+            .as_mut()
+            .expect("The camera needs a focal length before it can have an f-number");
+
+        lens.f_number = Some(f_number);
+    }
+
+    /// Lets the ISO, the f-number and the time the shutter is open
+    /// set how bright the image is, like a real camera. This is added
+    /// to set_exposure. Bright daylight is a radiance of about 1, so
+    /// f/16 with a shutter time of 1 / iso keeps the brightness.
+    /// Without an f-number from set_f_number the exposure is worked
+    /// out at f/16.
+    ///
+    /// # Panics:
+    /// This panics if iso is not positive.
+    pub fn set_iso(&mut self, iso: f64) {
+        assert!(iso > 0.0, "The ISO must be positive. {iso} is invalid.");
+
+        self.iso = Some(iso);
+    }
+
+    /// Sets the cameras focus distance, this replaces any focus
+    /// distance keyframes
     pub fn set_focus_dist(&mut self, fd: f64) {
-        self.focus_dist = fd;
+        self.focus_dist = ScalarTimeline::new(fd);
+    }
 
-        self.fix_viewport();
+    /// Renders a left and a right eye for 3D. The eyes sit interocular
+    /// apart along the camera's sideways axis and follow look_from, so
+    /// moving cameras work. Both eyes see the same frame at the
+    /// convergence distance, things closer than it pop out of the
+    /// screen. Use f64::INFINITY for parallel eyes. With the
+    /// equirectangular projection this renders omni-directional
+    /// stereo for VR, where the eyes circle around look_from.
+    ///
+    /// # Panics:
+    /// This panics if interocular or convergence is not positive.
+    pub fn set_stereo(&mut self, interocular: f64, convergence: f64, layout: StereoLayout) {
+        assert!(
+            interocular > 0.0,
+            "The interocular distance must be positive. {interocular} is invalid."
+        );
+        assert!(
+            convergence > 0.0,
+            "The convergence distance must be positive. {convergence} is invalid."
+        );
+
+        self.stereo = Some(Stereo {
+            interocular,
+            convergence,
+            layout,
+        });
+    }
+
+    /// Goes back to rendering a single image
+    pub fn clear_stereo(&mut self) {
+        self.stereo = None;
+    }
+
+    /// What is added to the file name of each image a render saves.
+    /// This is two names for stereo renders to separate files.
+    pub fn output_suffixes(&self) -> Vec<&'static str> {
+        match &self.stereo {
+            Some(Stereo {
+                layout: StereoLayout::SeparateFiles,
+                ..
+            }) => vec![Eye::Left.suffix(), Eye::Right.suffix()],
+            _ => vec![""],
+        }
     }
 
     /// Changes the number of threads a camera will render with
@@ -262,58 +753,334 @@ impl Camera {
         self.thread_count = threads;
     }
 
-    /// This causes the camera to render an image to stdout. Note
-    /// that this will truncate the file. Be careful
+    /// Sets the width and height in pixels of the tiles the
+    /// render threads claim. Smaller tiles balance the work better
+    /// while larger tiles have less scheduling overhead.
+    ///
+    /// # Panics:
+    /// This panics if size is 0.
+    pub fn set_tile_size(&mut self, size: u32) {
+        assert!(size > 0, "The camera cannot render with empty tiles.");
+
+        self.tile_size = size;
+    }
+
+    /// Sets the order the tiles are rendered in
+    pub fn set_tile_order(&mut self, order: TileOrder) {
+        self.tile_order = order;
+    }
+
+    /// Only renders the pixels from (x0, y0) up to but not including
+    /// (x1, y1). The projection is the same as the full frame's, so
+    /// the pixels in the window match a full render. output picks if
+    /// the saved image is just the window or the whole frame with
+    /// black around the window.
+    ///
+    /// # Panics:
+    /// This panics if the window is empty or reaches outside of the image.
+    pub fn set_crop_window(&mut self, x0: u32, y0: u32, x1: u32, y1: u32, output: CropOutput) {
+        let (iw, ih) = (self.viewport.image_width, self.viewport.image_height);
+        assert!(
+            x0 < x1 && y0 < y1,
+            "The crop window ({x0}, {y0}) to ({x1}, {y1}) is empty."
+        );
+        assert!(
+            x1 <= iw && y1 <= ih,
+            "The crop window ({x0}, {y0}) to ({x1}, {y1}) does not fit in the {iw}x{ih} image."
+        );
+
+        self.crop = Some(CropWindow {
+            region: Tile::new(x0, y0, x1, y1),
+            output,
+        });
+    }
+
+    /// Goes back to rendering the full frame
+    pub fn clear_crop_window(&mut self) {
+        self.crop = None;
+    }
+
+    /// Sets the operator that compresses bright light into the
+    /// range a display image can hold
+    ///
+    /// # Panics:
+    /// This panics if an extended Reinhard white point is not positive.
+    pub fn set_tone_mapper(&mut self, mapper: ToneMapper) {
+        if let ToneMapper::ExtendedReinhard { white_point } = mapper {
+            assert!(
+                white_point > 0.0,
+                "The white point must be positive. {white_point} is invalid."
+            );
+        }
+
+        self.tone_mapping.set_mapper(mapper);
+    }
+
+    /// Sets the exposure in stops. Each stop doubles the light, so
+    /// -1.0 halves it. This applies to every output format.
+    pub fn set_exposure(&mut self, stops: f64) {
+        self.tone_mapping.set_exposure(stops);
+    }
+
+    /// Sets how the 8 and 16 bit images are encoded
+    ///
+    /// # Panics:
+    /// This panics if a gamma is not positive.
+    pub fn set_transfer_function(&mut self, transfer: TransferFunction) {
+        if let TransferFunction::Gamma(gamma) = transfer {
+            assert!(
+                gamma > 0.0,
+                "The gamma must be positive. {gamma} is invalid."
+            );
+        }
+
+        self.tone_mapping.set_transfer(transfer);
+    }
+
+    /// Saves the partly rendered image to a checkpoint file every
+    /// interval while rendering. The checkpoint is kept next to the
+    /// image as `<image>.ckpt` and removed once the image is saved.
+    /// None turns checkpoints off, which is the default.
+    pub fn set_checkpoint_interval(&mut self, interval: Option<Duration>) {
+        self.checkpoint_interval = interval;
+    }
+
+    /// When resume is set a render first loads the checkpoint of
+    /// its image, if there is one, and only renders what is missing.
+    ///
+    /// A checkpoint only fits a render with the same settings, any
+    /// other checkpoint is an error. Resuming picks up whole tiles,
+    /// it cannot add samples to a finished image since the
+    /// checkpoint is gone once the image is saved.
+    pub fn set_resume(&mut self, resume: bool) {
+        self.resume = resume;
+    }
+
+    pub fn resume(&self) -> bool {
+        self.resume
+    }
+
+    pub fn tone_mapping(&self) -> &ToneMapping {
+        &self.tone_mapping
+    }
+
+    /// The image from the most recent render. This is black
+    /// until the camera renders.
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    /// This causes the camera to render an image and save it to
+    /// fname in the given format. Note that this will truncate the
+    /// file once the render is done. Be careful
+    ///
+    /// With resume set, a checkpoint left at `<fname>.ckpt` by an
+    /// earlier render of the same settings is continued.
+    ///
+    /// A stereo camera renders both eyes, see `Camera::set_stereo`.
+    /// The framebuffer then holds both eyes next to each other.
+    ///
+    /// The world is shared between all of the render threads. Its
+    /// bounding boxes must cover the whole shutter interval, see
+    /// `Hittables::update_bb`.
     ///
     /// # Error
-    /// Returns an error if the file cannot be opened.
-    pub fn render(&mut self, skybox: &Skybox, world: &Hittables, fname: &str) -> Result<(), Error> {
+    /// Returns an error if the file or a checkpoint cannot be written,
+    /// or the checkpoint being resumed cannot be read.
+    pub fn render(
+        &mut self,
+        skybox: &Skybox,
+        world: Arc<Hittables>,
+        lights: &LightList,
+        fname: &str,
+        format: &OutputFormat,
+    ) -> Result<(), Error> {
+        // Stereo renders both eyes, each with its own checkpoint
+        let stereo = self.stereo.clone();
+        let views = match &stereo {
+            Some(stereo) => stereo
+                .eye_file_names(fname)
+                .map(|(eye, name)| (Some(eye), name))
+                .to_vec(),
+            None => vec![(None, fname.to_string())],
+        };
+        let outputs = match &stereo {
+            Some(Stereo {
+                layout: StereoLayout::SeparateFiles,
+                ..
+            }) => views.iter().map(|(_, name)| name.clone()).collect(),
+            _ => vec![fname.to_string()],
+        };
+
+        let mut images = Vec::with_capacity(views.len());
+        for (index, (eye, name)) in views.iter().enumerate() {
+            self.eye = *eye;
+            let more_views = index + 1 < views.len();
+            let image = self.render_view(
+                skybox,
+                world.clone(),
+                lights,
+                &checkpoint_path(name),
+                more_views,
+            );
+            self.eye = None;
+
+            images.push(image?);
+        }
+
+        // Writing to file, the checkpoints are only needed until then
+        let tone_mapping = self.output_tone_mapping();
+        self.framebuffer = match &stereo {
+            Some(stereo) => {
+                if stereo.layout == StereoLayout::SeparateFiles {
+                    for (image, output) in images.iter().zip(&outputs) {
+                        image.save(output, format, &tone_mapping)?;
+                    }
+                }
+                stereo.combine(&images[0], &images[1])
+            }
+            None => images.pop().unwrap(),
+        };
+        if outputs.len() == 1 {
+            self.framebuffer.save(fname, format, &tone_mapping)?;
+        }
+
+        for (_, name) in &views {
+            match fs::remove_file(checkpoint_path(name)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders the image one eye sees, or the only image of a mono
+    /// camera. When more_views follow the finished view is kept in
+    /// its checkpoint so a resumed render can skip it.
+    fn render_view(
+        &mut self,
+        skybox: &Skybox,
+        world: Arc<Hittables>,
+        lights: &LightList,
+        ckpt_path: &str,
+        more_views: bool,
+    ) -> Result<Framebuffer, Error> {
         let iw = self.viewport.image_width;
         let ih = self.viewport.image_height;
 
-        // Make the file or truncate an existing one
-        let f = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(fname)?;
-
-        let mut bw = BufWriter::new(f);
+        let region = match &self.crop {
+            Some(crop) => crop.sampled_region(self.filter.apron(), iw, ih),
+            None => Tile::new(0, 0, iw, ih),
+        };
+        let tiles = tiles::build_tiles(&region, self.tile_size, &self.tile_order);
+        let tile_count = tiles.len();
+
+        // Pick up where a stopped render left off
+        let (min_samples, samples, noise_threshold) = match &self.adaptive {
+            Some(a) => (a.min_samples, a.max_samples, a.noise_threshold),
+            None => (self.samples, self.samples, 0.0),
+        };
+        let key = RenderKey {
+            width: iw,
+            height: ih,
+            region: region.clone(),
+            frame: self.frame as u64,
+            seed: self.seed,
+            samples,
+            min_samples,
+            noise_threshold,
+            sampling_method: self.sampling_method.clone(),
+            filter: self.filter.kind.clone(),
+            filter_radius: self.filter.radius,
+            tile_size: self.tile_size,
+            tile_order: self.tile_order.clone(),
+            tile_count: tile_count as u64,
+            integrator: self.integrator.key(),
+        };
+        let mut next_tile = 0;
+        let mut framebuffer = Framebuffer::new(iw, ih);
+
+        if self.resume {
+            match checkpoint::load(ckpt_path, &key) {
+                Ok(ckpt) => {
+                    next_tile = ckpt.tiles_done;
+                    framebuffer = ckpt.framebuffer;
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
 
         // Render
-        let (mut threads, mut sender) = self.thread_setup(skybox, world);
+        let (mut threads, mut sender, results) =
+            self.thread_setup(skybox, world, lights, region.pixel_count());
 
-        writeln!(bw, "P3\n{iw} {ih}\n255")?;
+        // Dispatching jobs, one tile at a time
+        for (index, tile) in tiles.into_iter().enumerate().skip(next_tile) {
+            let thread_info = ThreadInfo::new(index, tile);
 
-        // Dispatching jobs
-        for j in 0..ih {
-            for i in 0..iw {
-                // decimal values for each color from 0.0 to 1.0
-                let thread_info = ThreadInfo::new(i, j);
-
-                sender.as_ref().unwrap().send(thread_info).unwrap();
-            }
+            sender.as_ref().unwrap().send(thread_info).unwrap();
         }
 
-        // Waiting for threads
+        // No more jobs, the threads stop once the queue is empty
         drop(sender.take());
 
+        // Collect the tiles as they finish. This ends when every thread
+        // has hung up its result sender. Neighbouring tiles overlap, so
+        // they are merged in the order they were sent out. Otherwise
+        // the float sums would depend on which thread finished first.
+        let mut finished = BTreeMap::new();
+        let mut last_checkpoint = Instant::now();
+        for result in results {
+            finished.insert(result.index, result.film);
+
+            while let Some(film) = finished.remove(&next_tile) {
+                framebuffer.merge_tile(film);
+                next_tile += 1;
+            }
+
+            if let Some(interval) = self.checkpoint_interval
+                && last_checkpoint.elapsed() >= interval
+            {
+                checkpoint::save(ckpt_path, &key, next_tile, &framebuffer)?;
+                last_checkpoint = Instant::now();
+            }
+        }
+        debug_assert_eq!(next_tile, tile_count);
+
         for thread in threads.drain(..) {
             thread.join().unwrap();
         }
 
-        // Writing to file
-        for j in 0..ih {
-            for i in 0..iw {
-                let color = self.results.remove(&(i, j)).unwrap().1;
-                writeln!(bw, "{color}")?;
+        self.mp.clear().unwrap();
+
+        if more_views && self.checkpoint_interval.is_some() {
+            checkpoint::save(ckpt_path, &key, next_tile, &framebuffer)?;
+        }
+
+        // Only the window was sampled, the pixels around it are partial
+        if let Some(crop) = &self.crop {
+            match crop.output {
+                CropOutput::Cropped => framebuffer = framebuffer.crop(&crop.region),
+                CropOutput::FullFrame => framebuffer.clear_outside(&crop.region),
             }
         }
 
-        bw.flush()?;
-        self.mp.clear().unwrap();
+        Ok(framebuffer)
+    }
 
-        Ok(())
+    /// The tone mapping with the exposure of the ISO added
+    fn output_tone_mapping(&self) -> ToneMapping {
+        let mut tone_mapping = self.tone_mapping.clone();
+
+        if let Some(iso) = self.iso {
+            let f_number = self.lens.as_ref().and_then(|lens| lens.f_number);
+            let stops = exposure_stops(f_number, iso, self.shutter_interval().size());
+            tone_mapping.set_exposure(tone_mapping.exposure() + stops);
+        }
+
+        tone_mapping
     }
 
     pub(super) fn get_from(&self, t: f64) -> Point3 {
@@ -334,24 +1101,47 @@ impl Clone for Camera {
             viewport: self.viewport.clone(),
             vfov: self.vfov.clone(),
             aspect_ratio: self.aspect_ratio,
+            projection: self.projection.clone(),
 
             // Look targets
             look_from: self.look_from.clone(),
             look_at: self.look_at.clone(),
             vup: self.vup.clone(),
+            roll: self.roll.clone(),
+            bank: self.bank.clone(),
 
             // defocus vars
             defocus_angle: self.defocus_angle.clone(),
-            focus_dist: self.focus_dist,
+            focus_dist: self.focus_dist.clone(),
+            lens: self.lens.clone(),
+            iso: self.iso,
+            aperture: self.aperture.clone(),
+
+            stereo: self.stereo.clone(),
+            eye: self.eye,
 
             // sampling
             samples: self.samples,
+            adaptive: self.adaptive.clone(),
             sampling_method: self.sampling_method.clone(),
+            filter: self.filter.clone(),
+            integrator: self.integrator.clone(),
             max_depth: self.max_depth,
+            roulette_depth: self.roulette_depth,
+            seed: self.seed,
 
             // Clones have no threads
             thread_count: 0,
-            results: Arc::clone(&self.results),
+            tile_size: self.tile_size,
+            tile_order: self.tile_order.clone(),
+            crop: self.crop.clone(),
+
+            // Clones do not render so they do not carry the image
+            framebuffer: Framebuffer::new(0, 0),
+            tone_mapping: self.tone_mapping.clone(),
+
+            checkpoint_interval: self.checkpoint_interval,
+            resume: self.resume,
 
             mp: self.mp.clone(),
             sty: self.sty.clone(),
@@ -363,35 +1153,201 @@ impl Clone for Camera {
     }
 }
 
-/// Later change sampling so I can modify the sampling method
-/// to test different effects on image quality
-#[inline]
-fn sample_square() -> Vec3 {
-    // TODO: RNG may be too slow. But it is thread safe for the future
-    let mut rng = rand::rng();
-    let x = rng.random::<f64>() - 0.5;
-    let y = rng.random::<f64>() - 0.5;
-
-    Vec3::new(x, y, 0.0)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        materials::{Materials, lambertian::Lambertian},
+        objects::sphere::Sphere,
+        timeline::TransformSpace,
+        utils::Color,
+    };
+
+    pub(super) fn tiny_camera(threads: usize, seed: u64) -> Camera {
+        let mut cam = Camera::new(1.0, 24, 24.0, 180.0, threads);
+        cam.look_from(Point3::new(0.0, 0.0, 3.0));
+        cam.look_at(Point3::origin());
+        cam.set_defocus_angle(2.0);
+        cam.set_samples(4);
+        cam.set_pixel_filter(PixelFilter::Mitchell, 2.0);
+        cam.set_tile_size(5);
+        cam.set_seed(seed);
+
+        cam
+    }
+
+    pub(super) fn render_tiny(cam: &mut Camera, name: &str) -> Vec<(f64, f64, f64)> {
+        let world = Arc::new(Hittables::Sphere(Sphere::new(
+            Point3::origin(),
+            1.0,
+            Materials::Lambertian(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5), 1.0)),
+        )));
+        let fname =
+            std::env::temp_dir().join(format!("crucible_{name}_{}.ppm", std::process::id()));
+        let fname = fname.to_str().unwrap();
+
+        cam.render(
+            &Skybox::Default,
+            world,
+            &LightList::default(),
+            fname,
+            &OutputFormat::Ppm,
+        )
+        .unwrap();
+        std::fs::remove_file(fname).unwrap();
+
+        cam.framebuffer()
+            .pixels()
+            .iter()
+            .map(|p| (p.r(), p.g(), p.b()))
+            .collect()
+    }
 
     #[test]
-    fn ray_at_test() {
-        let r = Ray::new(Point3::origin(), Point3::new(2.0, -3.0, 1.5));
+    fn seeded_renders_ignore_threads() {
+        let single = render_tiny(&mut tiny_camera(1, 3), "seed_single");
 
-        assert_eq!(r.at(2.0), Point3::new(4.0, -6.0, 3.0));
+        assert_eq!(single, render_tiny(&mut tiny_camera(3, 3), "seed_threads"));
+        assert_ne!(single, render_tiny(&mut tiny_camera(1, 4), "seed_other"));
+    }
+
+    #[test]
+    fn lens_keyframes() {
+        let mut cam = Camera::new(1.0, 16, 24.0, 180.0, 1);
+        cam.look_from(Point3::origin());
+        cam.look_at(Point3::new(0.0, 0.0, -1.0));
+        let corner = |cam: &Camera, t: f64| {
+            let ray = cam.generate_ray(0, 0, (-0.5, -0.5), t, (0.0, 0.0)).unwrap();
+            ray.direction().clone().unit_vector()
+        };
+
+        // Zooming from 90 to 30 degrees passes 60 degrees halfway
+        cam.set_vfov(60.0);
+        let expected = corner(&cam, 0.0);
+        cam.set_vfov(90.0);
+        cam.add_vfov_keyframe(30.0, 1.0, InterpolationType::LERP);
+        assert!((corner(&cam, 0.5) - expected).length() < 1e-9);
+
+        // A focus pull moves the plane the rays meet on but not the view
+        let zoomed = corner(&cam, 1.0);
+        cam.add_focus_dist_keyframe(2.0, 1.0, InterpolationType::NERP);
+        let pulled = cam
+            .generate_ray(0, 0, (-0.5, -0.5), 1.0, (0.0, 0.0))
+            .unwrap();
+        assert!((pulled.direction().z() + 2.0).abs() < 1e-9);
+        assert!((corner(&cam, 1.0) - zoomed).length() < 1e-9);
+    }
+
+    #[test]
+    fn res_matches_the_saved_images() {
+        let mut cam = Camera::new(2.0, 16, 24.0, 180.0, 1);
+        assert_eq!(cam.get_res(), (16, 8));
+
+        cam.set_stereo(0.2, 5.0, StereoLayout::SideBySide);
+        assert_eq!(cam.get_res(), (32, 8));
+        cam.set_stereo(0.2, 5.0, StereoLayout::OverUnder);
+        assert_eq!(cam.get_res(), (16, 16));
+
+        cam.set_crop_window(2, 1, 8, 5, CropOutput::Cropped);
+        assert_eq!(cam.get_res(), (6, 8));
+    }
+
+    #[test]
+    fn fov_replaces_lens() {
+        let mut cam = Camera::new(1.0, 16, 24.0, 180.0, 1);
+        cam.set_focal_length(50.0, SensorSize::FullFrame);
+        cam.set_f_number(2.8);
+        cam.set_vfov(60.0);
+        assert!(cam.lens.is_none());
+
+        // Without an f-number the ISO is worked out at f/16
+        cam.set_iso(200.0);
+        let shutter = cam.shutter_interval().size();
+        let stops = (shutter * 200.0).log2();
+        assert!((cam.output_tone_mapping().exposure() - stops).abs() < 1e-12);
     }
 
     #[test]
-    fn average_color_test() {
-        let cv = vec![Color::new(0.0, 1.0, 0.0), Color::new(0.5, 0.5, 1.0)];
+    fn integrators_take_the_camera_depth() {
+        let mut cam = Camera::new(1.0, 16, 24.0, 180.0, 1);
+        cam.set_max_depth(50);
+        cam.set_roulette_depth(None);
+        cam.set_integrator(Integrators::from_name("direct").unwrap());
+        assert_eq!(cam.integrator.key(), [1, 50, 0]);
+
+        cam.set_integrator(Integrators::Path(PathIntegrator::new(10, Some(3))));
+        assert_eq!(cam.integrator.key(), [0, 50, u64::MAX]);
+    }
+
+    #[test]
+    fn roll_and_banking() {
+        let mut cam = Camera::new(1.0, 16, 24.0, 180.0, 1);
+        cam.look_from(Point3::origin());
+        cam.look_at(Point3::new(0.0, 0.0, -100.0));
+
+        // A quarter turn puts the right side of the frame on top
+        cam.set_roll(90.0);
+        assert!((cam.basis(0.0).u - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        cam.set_roll(0.0);
+
+        // Fly forward, then turn left
+        let world = TransformSpace::World;
+        cam.look_from
+            .translate_z(-10.0, 1.0, InterpolationType::LERP, world.clone());
+        cam.look_from
+            .translate_x(0.0, 1.0, InterpolationType::NERP, world.clone());
+        cam.look_from
+            .translate_x(-10.0, 2.0, InterpolationType::LERP, world);
+        assert_eq!(cam.bank_angle(1.0), 0.0);
+
+        cam.set_banking(1.0, 0.5);
+        assert!(cam.bank_angle(0.5).abs() < 1e-9);
+        assert!(cam.bank_angle(1.0) > 0.5);
+        assert!(cam.basis(1.0).u.y() > 0.0);
+
+        // Smoothing wider than the time since the start stays on the
+        // timeline
+        cam.set_banking(1.0, 4.0);
+        assert!(cam.bank_angle(0.0).is_finite());
+    }
 
-        let c = ray_casting::average_samples(cv);
+    #[test]
+    fn stereo_eyes() {
+        let mut cam = Camera::new(1.0, 16, 24.0, 180.0, 1);
+        cam.look_from(Point3::origin());
+        cam.look_at(Point3::new(0.0, 0.0, -1.0));
+        cam.set_stereo(0.2, 5.0, StereoLayout::SideBySide);
+
+        // The eyes cross at the convergence distance
+        cam.eye = Some(Eye::Left);
+        let left = cam.generate_ray(7, 7, (0.5, 0.5), 0.0, (0.0, 0.0)).unwrap();
+        assert!((left.origin().x() + 0.1).abs() < 1e-9);
+        let t = -5.0 / left.direction().z();
+        assert!(left.at(t).x().abs() < 1e-9);
+
+        // Omni-directional eyes sit sideways to where they look
+        cam.set_stereo(0.2, f64::INFINITY, StereoLayout::OverUnder);
+        cam.set_projection(Projection::Equirectangular);
+        cam.eye = Some(Eye::Right);
+        let side = cam.generate_ray(3, 7, (0.5, 0.5), 0.0, (0.0, 0.0)).unwrap();
+        assert!(side.origin().dot(side.direction()).abs() < 1e-9);
+        assert!((side.origin().length() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stereo_render_layout() {
+        let mut cam = tiny_camera(1, 3);
+        cam.set_stereo(0.2, 3.0, StereoLayout::SideBySide);
+        let both = render_tiny(&mut cam, "stereo");
 
-        assert_eq!(c, Color::new(0.25, 0.75, 0.5));
+        assert_eq!(cam.framebuffer().width(), 48);
+        assert_ne!(both[12 * 48 + 12], both[12 * 48 + 36]);
+    }
+
+    #[test]
+    fn ray_at_test() {
+        let r = Ray::new(Point3::origin(), Point3::new(2.0, -3.0, 1.5));
+
+        assert_eq!(r.at(2.0), Point3::new(4.0, -6.0, 3.0));
     }
 }
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+
+/// The image formats a render can be saved as. The 8 and 16 bit
+/// formats are gamma corrected for display while the float formats
+/// keep the linear radiance untouched for grading.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// ASCII P3 ppm, 8 bits per channel
+    Ppm,
+    /// PNG with 8 bits per channel
+    #[value(name = "png", alias = "png8")]
+    Png8,
+    /// PNG with 16 bits per channel
+    Png16,
+    /// 32 bit float OpenEXR
+    Exr,
+    /// Radiance RGBE .hdr
+    Hdr,
+}
+
+impl OutputFormat {
+    /// Picks the format from the extension on a file name. A .png
+    /// file is treated as 8 bit. Returns None if the file has no
+    /// extension or the extension is not supported.
+    pub fn from_path(fname: &str) -> Option<OutputFormat> {
+        let extension = Path::new(fname).extension()?.to_str()?;
+
+        match extension.to_lowercase().as_str() {
+            "ppm" => Some(OutputFormat::Ppm),
+            "png" => Some(OutputFormat::Png8),
+            "exr" => Some(OutputFormat::Exr),
+            "hdr" => Some(OutputFormat::Hdr),
+            _ => None,
+        }
+    }
+
+    /// Picks the format from a name such as "png16", this is
+    /// meant for command line flags. Returns None if the name
+    /// is not a format.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        OutputFormat::from_str(name, true).ok()
+    }
+
+    /// The file extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Ppm => "ppm",
+            OutputFormat::Png8 | OutputFormat::Png16 => "png",
+            OutputFormat::Exr => "exr",
+            OutputFormat::Hdr => "hdr",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(OutputFormat::from_path("out.png"), Some(OutputFormat::Png8));
+        assert_eq!(
+            OutputFormat::from_path("renders/out.EXR"),
+            Some(OutputFormat::Exr)
+        );
+        assert_eq!(OutputFormat::from_path("out"), None);
+        assert_eq!(OutputFormat::from_path("renders.v2/out"), None);
+        assert_eq!(OutputFormat::from_path("out.jpg"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use clap::{ValueEnum, builder::PossibleValue};
+
+/// Common camera sensor sizes. A lens gives a wider view on a
+/// larger sensor.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensorSize {
+    /// 36 x 24 mm, the size of a 35mm still frame
+    FullFrame,
+    /// 24.89 x 18.66 mm, the common cinema film gate
+    Super35,
+    /// 23.6 x 15.6 mm
+    ApsC,
+    /// Any other sensor, measured in mm
+    Custom { width: f64, height: f64 },
+}
+
+impl SensorSize {
+    /// Picks a sensor from a name such as "super35", this is meant
+    /// for command line flags. Returns None if the name is not a sensor.
+    pub fn from_name(name: &str) -> Option<SensorSize> {
+        SensorSize::from_str(name, true).ok()
+    }
+
+    /// The width and height of the sensor in mm
+    pub fn dimensions(&self) -> (f64, f64) {
+        match self {
+            SensorSize::FullFrame => (36.0, 24.0),
+            SensorSize::Super35 => (24.89, 18.66),
+            SensorSize::ApsC => (23.6, 15.6),
+            SensorSize::Custom { width, height } => (*width, *height),
+        }
+    }
+}
+
+/// The command line names, custom sensors have none
+impl ValueEnum for SensorSize {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[SensorSize::FullFrame, SensorSize::Super35, SensorSize::ApsC]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            SensorSize::FullFrame => Some(
+                PossibleValue::new("full-frame")
+                    .alias("fullframe")
+                    .help("36 x 24 mm"),
+            ),
+            SensorSize::Super35 => Some(PossibleValue::new("super35").help("24.89 x 18.66 mm")),
+            SensorSize::ApsC => Some(
+                PossibleValue::new("aps-c")
+                    .alias("apsc")
+                    .help("23.6 x 15.6 mm"),
+            ),
+            SensorSize::Custom { .. } => None,
+        }
+    }
+}
+
+/// A lens of a real camera. The world is measured in meters
+/// when the aperture is turned into a size.
+#[derive(Debug, Clone)]
+pub(super) struct PhysicalLens {
+    /// In mm
+    pub(super) focal_length: f64,
+    pub(super) sensor: SensorSize,
+    pub(super) f_number: Option<f64>,
+}
+
+impl PhysicalLens {
+    /// The horizontal field of view in degrees. The sensor width
+    /// covers the width of the image.
+    pub(super) fn hfov(&self) -> f64 {
+        let (width, _) = self.sensor.dimensions();
+
+        2.0 * (width / (2.0 * self.focal_length)).atan().to_degrees()
+    }
+
+    /// The radius of the aperture in meters, None if the lens has
+    /// no f-number
+    pub(super) fn aperture_radius(&self) -> Option<f64> {
+        self.f_number.map(|n| self.focal_length / n / 2.0 / 1000.0)
+    }
+}
+
+/// How many stops the ISO and shutter time brighten the image.
+/// The sunny 16 rule is 0 stops: at f/16 with a shutter time of
+/// 1 / ISO a radiance of 1 keeps its brightness. Without an
+/// f-number the camera is treated as f/16.
+pub(super) fn exposure_stops(f_number: Option<f64>, iso: f64, shutter_time: f64) -> f64 {
+    let n = f_number.unwrap_or(16.0);
+
+    (256.0 * shutter_time * iso / (n * n)).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lens_math() {
+        let lens = PhysicalLens {
+            focal_length: 50.0,
+            sensor: SensorSize::FullFrame,
+            f_number: Some(16.0),
+        };
+
+        // A 50mm lens on full frame sees about 39.6 degrees across
+        assert!((lens.hfov() - 39.5978).abs() < 1e-3);
+        assert!((lens.aperture_radius().unwrap() - 0.0015625).abs() < 1e-12);
+        assert!(exposure_stops(lens.f_number, 100.0, 1.0 / 100.0).abs() < 1e-12);
+        assert!((exposure_stops(lens.f_number, 100.0, 1.0 / 50.0) - 1.0).abs() < 1e-12);
+        assert!((exposure_stops(Some(8.0), 100.0, 1.0 / 100.0) - 2.0).abs() < 1e-12);
+        assert!(exposure_stops(None, 100.0, 1.0 / 100.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,25 @@
+/// How the camera maps pixels to the directions it looks in. Every
+/// projection follows the look_from and look_at timelines, so they
+/// all work with moving cameras and motion blur.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Projection {
+    /// A pinhole or thin lens camera, this is the default. The field
+    /// of view comes from `Camera::set_vfov`.
+    Perspective,
+    /// Parallel rays, the image covers scale world units vertically.
+    /// Objects keep their size no matter how far away they are.
+    Orthographic { scale: f64 },
+    /// A circular fisheye where the angle from the center grows
+    /// evenly with the distance from the center of the image. fov is
+    /// the angle in degrees across the circle, which fits in the
+    /// shorter side of the image.
+    FisheyeEquidistant { fov: f64 },
+    /// A circular fisheye that keeps the area of things the same,
+    /// like most real fisheye lenses. fov works like it does for
+    /// FisheyeEquidistant.
+    FisheyeEquisolid { fov: f64 },
+    /// A 360 x 180 degree panorama in the latitude longitude layout
+    /// that spherical skyboxes use. The image should be twice as
+    /// wide as it is tall.
+    Equirectangular,
+}
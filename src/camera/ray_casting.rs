@@ -1,12 +1,11 @@
-use std::f64::consts::PI;
-
-use rand::Rng;
+use std::sync::Arc;
 
 use crate::{
-    camera::{Camera, SamplingMethod, sample_square},
+    camera::{Camera, Sampler, adaptive::PixelVariance, filter::TileFilm},
+    lights::LightList,
     objects::Hittables,
     scene::Skybox,
-    utils::{Color, Interval, Point3, Vec3},
+    utils::{Point3, Radiance, Vec3},
 };
 
 /// Ray represents a ray of light with a direction
@@ -17,6 +16,7 @@ pub struct Ray {
     origin: Point3,
     direction: Vec3,
     tm: f64,
+    medium_sample: Option<f64>,
 }
 
 impl Ray {
@@ -26,6 +26,7 @@ impl Ray {
             origin,
             direction,
             tm: 0.0,
+            medium_sample: None,
         }
     }
 
@@ -35,9 +36,18 @@ impl Ray {
             origin,
             direction,
             tm,
+            medium_sample: None,
         }
     }
 
+    /// Gives the ray a number in [0, 1) from the sampler that media
+    /// along it scatter by, see Sampler::medium. Rays without one
+    /// make up their own from where they point.
+    pub fn with_medium_sample(mut self, u: f64) -> Ray {
+        self.medium_sample = Some(u);
+        self
+    }
+
     pub fn origin(&self) -> &Point3 {
         &self.origin
     }
@@ -50,6 +60,10 @@ impl Ray {
         self.tm
     }
 
+    pub fn medium_sample(&self) -> Option<f64> {
+        self.medium_sample
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         let dir_clone: Vec3 = self.direction.clone();
         let orig_clone = self.origin.clone();
@@ -59,115 +73,80 @@ impl Ray {
     }
 }
 
+/// Everything a ray can run into: the objects, the lights that are
+/// sampled directly and the sky behind them. The render threads share
+/// one of these.
+pub struct RenderWorld {
+    pub(super) objects: Arc<Hittables>,
+    pub(super) lights: LightList,
+    pub(super) skybox: Skybox,
+}
+
+impl RenderWorld {
+    pub fn new(objects: Arc<Hittables>, lights: LightList, skybox: Skybox) -> RenderWorld {
+        RenderWorld {
+            objects,
+            lights,
+            skybox,
+        }
+    }
+}
+
 /// Here are all the implementations pertaining to casting a ray for the camera
 impl Camera {
     pub(super) fn cast_ray(
         &self,
         render_i: u32,
         render_j: u32,
-        max_depth: u32,
-        sb: &Skybox,
-        world: &mut Hittables,
-    ) -> Color {
-        // Store the colors from each sample
-        let mut sample_colors = Vec::new();
-        let mut rng = rand::rng();
-
-        // Compute current frame time:
-        let current_time = (self.frame as f64) * (1.0 / self.frame_rate);
-        // Compute the shutter length from the shutter angle
-        let shutter_length = (self.shutter_angle / 360.0) * (1.0 / self.frame_rate);
+        world: &RenderWorld,
+        film: &mut TileFilm,
+    ) {
+        // The time the shutter is open for this frame
+        let shutter = self.shutter_interval();
+
+        // A fixed sample count is the same as adaptive sampling that
+        // can never stop early
+        let (min_samples, max_samples, threshold) = match &self.adaptive {
+            Some(a) => (a.min_samples, a.max_samples, a.noise_threshold),
+            None => (self.samples, self.samples, 0.0),
+        };
+        let mut variance = PixelVariance::new();
+        // Every frame of a movie gets its own noise
+        let seed = self.seed ^ (self.frame as u64).wrapping_mul(0x9e3779b97f4a7c15);
+        let mut sampler = Sampler::new(self.sampling_method.clone(), max_samples, seed);
+        // Pixels only stop between batches, each batch is stratified
+        // on its own so a pixel that stops early is not biased
+        sampler.set_batch_size(min_samples);
 
         // loop and sample
-        for _ in 0..self.samples {
-            // Generate random time sample:
-            let time_sample = current_time + rng.random_range(0.0..=shutter_length);
-
-            // Get camera center at the time_sample
-            let cc = self.get_from(time_sample);
+        for n in 1..=max_samples {
+            sampler.start_pixel_sample(render_i, render_j, n - 1);
 
-            // Sample based on the method
-            let offset = match self.sampling_method {
-                SamplingMethod::Square => sample_square(),
-            };
+            // Place the sample in the shutter interval
+            let time_sample = shutter.min() + sampler.time() * shutter.size();
 
-            let ps = self.get_pixel_pos(render_i, render_j, offset, time_sample);
+            let (ox, oy) = sampler.pixel_offset();
+            let lens = sampler.lens();
 
-            let ray_orig = if self.defocus_angle.get_angle() <= 0.0 {
-                cc.clone()
-            } else {
-                self.defocus_disk_sample(time_sample)
+            // Samples outside of a fisheye's circle see nothing
+            let sample = match self.generate_ray(render_i, render_j, (ox, oy), time_sample, lens) {
+                Some(ray_cast) => self.integrator.radiance(ray_cast, world, &mut sampler),
+                None => Radiance::black(),
             };
-
-            let ray_dir = ps - ray_orig.clone();
-            let ray_cast = Ray::new_at_time(ray_orig, ray_dir, time_sample);
-            sample_colors.push(ray_color(ray_cast, max_depth, sb, world));
-        }
-
-        average_samples(sample_colors)
-    }
-}
-
-// Function that causes ray bounces and computes the color of a ray_cast
-pub fn ray_color(r: Ray, depth: u32, sb: &Skybox, world: &mut Hittables) -> Color {
-    // If we have reached the max bounces we no longer
-    // gather color contribution
-    if depth == 0 {
-        return Color::black();
-    }
-    // The interval starts at 0.001 to fix the 'shadow acne' behavior
-    let hit = world.hit(&r, &Interval::new(0.001, f64::INFINITY));
-
-    if let Some(h) = hit {
-        let mut attenuation = Color::black();
-
-        let scatter = h.material().scatter(&r, &h, &mut attenuation);
-
-        if let Some(s) = scatter {
-            return attenuation * ray_color(s, depth - 1, sb, world);
+            variance.add(sample.luminance());
+
+            // Spread the sample to the pixels around where it landed
+            film.add_sample(
+                render_i as f64 + ox,
+                render_j as f64 + oy,
+                &sample,
+                &self.filter,
+            );
+
+            let end_of_batch = n.is_multiple_of(min_samples);
+            if end_of_batch && n < max_samples && variance.converged(threshold) {
+                break;
+            }
         }
-
-        return Color::black();
     }
-
-    match sb {
-        Skybox::Spherical(sky) => {
-            let unit_direction = r.direction().clone().unit_vector();
-            let theta = unit_direction.x().atan2(unit_direction.z());
-            let phi = unit_direction.y().asin();
-
-            let u = (theta / (2.0 * PI)) + 0.5;
-            let v = (phi / PI) + 0.5;
-
-            // Clamp then scale with the skyboxes size:
-            sky.get_color(u, v)
-        }
-        Skybox::Default => {
-            let unit_direction = r.direction().clone().unit_vector();
-            let a = 0.5 * (unit_direction.y() + 1.0);
-
-            (1.0 - a) * Color::white() + a * Color::new(0.5, 0.7, 1.0)
-        }
-    }
-}
-
-pub(super) fn average_samples(sample_colors: Vec<Color>) -> Color {
-    let mut r_tot = 0.0;
-    let mut g_tot = 0.0;
-    let mut b_tot = 0.0;
-
-    let sample_count = sample_colors.len();
-
-    for col in sample_colors {
-        r_tot += col.r();
-        g_tot += col.g();
-        b_tot += col.b();
-    }
-
-    // Take the average
-    r_tot /= sample_count as f64;
-    g_tot /= sample_count as f64;
-    b_tot /= sample_count as f64;
-
-    Color::new(r_tot, g_tot, b_tot)
 }
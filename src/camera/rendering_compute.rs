@@ -1,45 +1,72 @@
-use crate::{camera::Camera, utils::{Point3, Vec3}};
+use std::f64::consts::PI;
+
+use crate::{
+    camera::{Camera, Ray, projection::Projection},
+    utils::{Point3, Vec3},
+};
+
+/// The pull of gravity in meters per second squared, banking
+/// balances turns against it
+const GRAVITY: f64 = 9.81;
+
+/// The sideways, up and backwards axes of the camera
+pub(super) struct Basis {
+    pub(super) u: Vec3,
+    pub(super) v: Vec3,
+    pub(super) w: Vec3,
+}
 
 impl Camera {
-    // Call whenever any of these vars change
-    pub(super) fn fix_viewport(&mut self) {
-        let h = (self.vfov.get_angle() / 2.0).tan();
+    /// The focus distance at time t
+    #[inline]
+    fn focus_dist(&self, t: f64) -> f64 {
+        self.focus_dist.value_at(t)
+    }
 
-        self.viewport.viewport_height = 2.0 * h * self.focus_dist;
-        self.viewport.viewport_width = self.viewport.viewport_height
-            * (self.viewport.image_width as f64 / self.viewport.image_height as f64);
+    /// The height of the viewport on the focus plane. The field of
+    /// view and focus distance can be animated so this is found
+    /// for every ray.
+    #[inline]
+    fn viewport_height(&self, t: f64) -> f64 {
+        let h = (self.vfov.value_at(t).to_radians() / 2.0).tan();
+
+        2.0 * h * self.focus_dist(t)
+    }
+
+    #[inline]
+    fn viewport_width(&self, t: f64) -> f64 {
+        self.viewport_height(t)
+            * (self.viewport.image_width as f64 / self.viewport.image_height as f64)
     }
 
-    // TODO: Inlined for efficiency might not work when
-    // the camera can move
     /// Vector representing the horizontal viewport edge
     #[inline]
-    fn viewport_u(&self, t: f64) -> Vec3 {
-        self.viewport.viewport_width * self.u_basis(t)
+    fn viewport_u(&self, t: f64, basis: &Basis) -> Vec3 {
+        self.viewport_width(t) * basis.u.clone()
     }
 
     /// Vector representing the vertical viewport edge. It is
     /// negative since the coordinate for the image are opposite
     /// to the camera (we want our vec to point down)
     #[inline]
-    fn viewport_v(&self, t: f64) -> Vec3 {
-        self.viewport.viewport_height * (-self.v_basis(t))
+    fn viewport_v(&self, t: f64, basis: &Basis) -> Vec3 {
+        self.viewport_height(t) * (-basis.v.clone())
     }
 
     /// Subdivide the length of our viewport by pixels
     /// this gets the vector between two pixels in the
     /// x-axis.
     #[inline]
-    fn pixel_delta_u(&self, t: f64) -> Vec3 {
-        self.viewport_u(t) / self.viewport.image_width as f64
+    fn pixel_delta_u(&self, t: f64, basis: &Basis) -> Vec3 {
+        self.viewport_u(t, basis) / self.viewport.image_width as f64
     }
 
     /// Subdivide the length of our viewport by pixels
     /// this gets the vector between two pixels in the
     /// y-axis.
     #[inline]
-    fn pixel_delta_v(&self, t: f64) -> Vec3 {
-        self.viewport_v(t) / self.viewport.image_height as f64
+    fn pixel_delta_v(&self, t: f64, basis: &Basis) -> Vec3 {
+        self.viewport_v(t, basis) / self.viewport.image_height as f64
     }
 
     /// Compute the upper left hand corner. This uses the
@@ -47,40 +74,85 @@ impl Camera {
     /// the / 2.0 on the last two lines breaks generality of
     /// camera position.
     #[inline]
-    fn viewport_upperleft(&self, t: f64) -> Point3 {
+    fn viewport_upperleft(&self, t: f64, basis: &Basis) -> Point3 {
         let cc = self.get_from(t);
-        cc - (self.focus_dist * self.w_basis(t))
-            - self.viewport_u(t) / 2.0
-            - self.viewport_v(t) / 2.0
+        cc - (self.focus_dist(t) * basis.w.clone())
+            - self.viewport_u(t, basis) / 2.0
+            - self.viewport_v(t, basis) / 2.0
     }
 
     #[inline]
-    fn pixel_start_location(&self, t: f64) -> Point3 {
-        self.viewport_upperleft(t) + 0.5 * (self.pixel_delta_u(t) + self.pixel_delta_v(t))
+    fn pixel_start_location(&self, t: f64, basis: &Basis) -> Point3 {
+        self.viewport_upperleft(t, basis)
+            + 0.5 * (self.pixel_delta_u(t, basis) + self.pixel_delta_v(t, basis))
     }
 
     /// The camera can take an ij pair in the image and
     /// calculate its position relative to the camera
-    pub(super) fn get_pixel_pos(&self, i: u32, j: u32, offset: Point3, t: f64) -> Point3 {
-        self.pixel_start_location(t)
-            + ((i as f64 + offset.x()) * self.pixel_delta_u(t))
-            + ((j as f64 + offset.y()) * self.pixel_delta_v(t))
+    pub(super) fn get_pixel_pos(
+        &self,
+        i: u32,
+        j: u32,
+        offset: Point3,
+        t: f64,
+        basis: &Basis,
+    ) -> Point3 {
+        self.pixel_start_location(t, basis)
+            + ((i as f64 + offset.x()) * self.pixel_delta_u(t, basis))
+            + ((j as f64 + offset.y()) * self.pixel_delta_v(t, basis))
     }
 
     #[inline]
-    fn defocus_radius(&self) -> f64 {
-        self.focus_dist * (self.defocus_angle.get_angle() / 2.0).tan()
+    fn defocus_radius(&self, t: f64) -> f64 {
+        // A real aperture has a size of its own
+        if let Some(radius) = self.lens.as_ref().and_then(|lens| lens.aperture_radius()) {
+            return radius;
+        }
+
+        self.focus_dist(t) * (self.defocus_angle.value_at(t).to_radians() / 2.0).tan()
     }
 
-    // Basis vectors
+    /// The up vector at time t
     #[inline]
-    fn u_basis(&self, t: f64) -> Vec3 {
-        self.vup.cross(&self.w_basis(t)).unit_vector()
+    fn vup(&self, t: f64) -> Vec3 {
+        let [x, y, z] = &self.vup;
+        Vec3::new(x.value_at(t), y.value_at(t), z.value_at(t))
     }
 
-    #[inline]
-    fn v_basis(&self, t: f64) -> Vec3 {
-        self.w_basis(t).cross(&self.u_basis(t))
+    /// How far the camera leans into the turn it is in at time t, in
+    /// radians. A left turn leans left, which is a positive roll.
+    pub(super) fn bank_angle(&self, t: f64) -> f64 {
+        let Some(bank) = &self.bank else {
+            return 0.0;
+        };
+        let h = bank.smoothing / 2.0;
+
+        // The acceleration of look_from, a turn pulls sideways. The
+        // timeline starts at 0 so near the start the samples are
+        // moved forward to stay on it.
+        let mid = t.max(h);
+        let accel =
+            (self.get_from(mid + h) - 2.0 * self.get_from(mid) + self.get_from(mid - h)) / (h * h);
+        let side = self.vup(t).cross(&self.w_basis(t)).unit_vector();
+
+        (-accel.dot(&side) * bank.strength / GRAVITY).atan()
+    }
+
+    /// The axes of the camera at time t. Rays find these once since
+    /// the roll and banking walk the timelines.
+    pub(super) fn basis(&self, t: f64) -> Basis {
+        let w = self.w_basis(t);
+        let mut u = self.vup(t).cross(&w).unit_vector();
+
+        // Turning the right side of the frame up rolls counterclockwise
+        let roll = self.roll.value_at(t).to_radians() + self.bank_angle(t);
+        if roll != 0.0 {
+            let v = w.cross(&u);
+            u = roll.cos() * u + roll.sin() * v;
+        }
+
+        let v = w.cross(&u);
+        Basis { u, v, w }
     }
 
     #[inline]
@@ -92,22 +164,233 @@ impl Camera {
     }
 
     #[inline]
-    fn defocus_disk_u(&self, t: f64) -> Vec3 {
-        self.u_basis(t) * self.defocus_radius()
+    fn defocus_disk_u(&self, t: f64, basis: &Basis) -> Vec3 {
+        basis.u.clone() * self.defocus_radius(t)
     }
 
     #[inline]
-    fn defocus_disk_v(&self, t: f64) -> Vec3 {
-        self.v_basis(t) * self.defocus_radius()
+    fn defocus_disk_v(&self, t: f64, basis: &Basis) -> Vec3 {
+        basis.v.clone() * self.defocus_radius(t)
     }
 
-    // This might be repurposeable as disc sampling TODO
-    pub(super) fn defocus_disk_sample(&self, t: f64) -> Point3 {
-        let p = Point3::random_in_unit_disk();
+    /// Picks the ray origin in the aperture from a 2D sample. The
+    /// lens barrel can block it for rays near the edge of the frame,
+    /// see `Aperture::sample`.
+    pub(super) fn defocus_disk_sample(
+        &self,
+        t: f64,
+        lens: (f64, f64),
+        image_pos: (f64, f64),
+        basis: &Basis,
+    ) -> Option<Point3> {
+        let (px, py) = self.aperture.sample(lens, image_pos)?;
         let from = self.get_from(t);
 
-        from + (p.x() * self.defocus_disk_u(t)) + (p.y() * self.defocus_disk_v(t))
+        Some(from + (px * self.defocus_disk_u(t, basis)) + (py * self.defocus_disk_v(t, basis)))
+    }
+
+    /// Makes the ray for a sample in pixel (i, j). offset moves the
+    /// sample inside the pixel and lens picks a point on the lens.
+    /// Returns None if the sample is outside of a fisheye's circle or
+    /// the lens barrel blocks it.
+    pub(super) fn generate_ray(
+        &self,
+        i: u32,
+        j: u32,
+        offset: (f64, f64),
+        t: f64,
+        lens: (f64, f64),
+    ) -> Option<Ray> {
+        // Where the sample lands in the image, from 0 to 1
+        let x = (i as f64 + 0.5 + offset.0) / self.viewport.image_width as f64;
+        let y = (j as f64 + 0.5 + offset.1) / self.viewport.image_height as f64;
+
+        // The same spot measured from the center, 1 is a corner
+        let (iw, ih) = (
+            self.viewport.image_width as f64,
+            self.viewport.image_height as f64,
+        );
+        let half_diagonal = (iw * iw + ih * ih).sqrt() / 2.0;
+        let image_pos = (
+            (x - 0.5) * iw / half_diagonal,
+            (0.5 - y) * ih / half_diagonal,
+        );
+
+        let basis = self.basis(t);
+        let (origin, direction) = match &self.projection {
+            Projection::Perspective => {
+                let mut ps =
+                    self.get_pixel_pos(i, j, Vec3::new(offset.0, offset.1, 0.0), t, &basis);
+                let mut origin = self.lens_origin(t, lens, image_pos, &basis)?;
+
+                // Both eyes frame the same window at the convergence
+                // distance, so the focus plane shifts less than the eye
+                if let Some((side, convergence)) = self.eye_offset() {
+                    let shift = side * basis.u.clone();
+                    ps += (1.0 - self.focus_dist(t) / convergence) * shift.clone();
+                    origin += shift;
+                }
+
+                (origin.clone(), ps - origin)
+            }
+            Projection::Orthographic { scale } => {
+                let width = scale * iw / ih;
+                let side = self.eye_offset().map_or(0.0, |(side, _)| side);
+                let on_plane = self.get_from(t) + ((x - 0.5) * width + side) * basis.u.clone()
+                    - ((y - 0.5) * scale) * basis.v.clone();
+                let focus = on_plane.clone() - self.focus_dist(t) * basis.w.clone();
+
+                // The lens moves around the spot on the image plane
+                let lens_offset = self.lens_origin(t, lens, image_pos, &basis)? - self.get_from(t);
+                let origin = on_plane.clone() + lens_offset;
+
+                (origin.clone(), focus - origin)
+            }
+            Projection::FisheyeEquidistant { fov } | Projection::FisheyeEquisolid { fov } => {
+                // Distance from the center, 1 is the edge of the circle
+                let dx = (x - 0.5) * iw;
+                let dy = (0.5 - y) * ih;
+                let r = (dx * dx + dy * dy).sqrt() / (iw.min(ih) / 2.0);
+                if r > 1.0 {
+                    return None;
+                }
+
+                let half_fov = fov.to_radians() / 2.0;
+                let theta = match self.projection {
+                    Projection::FisheyeEquisolid { .. } => {
+                        2.0 * (r * (half_fov / 2.0).sin()).asin()
+                    }
+                    _ => r * half_fov,
+                };
+                let phi = dy.atan2(dx);
+
+                let direction = theta.sin() * phi.cos() * basis.u.clone()
+                    + theta.sin() * phi.sin() * basis.v.clone()
+                    - theta.cos() * basis.w.clone();
+
+                let side = self.eye_offset().map_or(0.0, |(side, _)| side);
+                (self.get_from(t) + side * basis.u.clone(), direction)
+            }
+            Projection::Equirectangular => {
+                // Laid out like spherical skyboxes, so longitude grows
+                // against u. Seen from a camera looking down +z with
+                // +y up the image can be loaded back as a skybox.
+                let longitude = (x - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - y) * PI;
+
+                let direction = latitude.cos() * longitude.sin() * -basis.u.clone()
+                    + latitude.sin() * basis.v.clone()
+                    - latitude.cos() * longitude.cos() * basis.w.clone();
+
+                let Some((side, convergence)) = self.eye_offset() else {
+                    return Some(Ray::new_at_time(self.get_from(t), direction, t));
+                };
+
+                // Omni-directional stereo: the eyes circle around
+                // look_from, always sideways to the direction they look.
+                // Shrinking the circle towards the poles keeps them from
+                // swapping places over the top of the sphere.
+                let horizontal =
+                    longitude.sin() * -basis.u.clone() - longitude.cos() * basis.w.clone();
+                let eye = (side * latitude.cos()) * horizontal.cross(&basis.v.clone());
+                let direction = if convergence.is_finite() {
+                    convergence * direction - eye.clone()
+                } else {
+                    direction
+                };
+
+                (self.get_from(t) + eye, direction)
+            }
+        };
+
+        Some(Ray::new_at_time(origin, direction, t))
+    }
+
+    /// How far right of look_from the eye being rendered sits along
+    /// with the convergence distance, None unless a stereo eye is
+    /// being rendered
+    fn eye_offset(&self) -> Option<(f64, f64)> {
+        let (eye, stereo) = (self.eye?, self.stereo.as_ref()?);
+
+        Some((eye.side() * stereo.interocular / 2.0, stereo.convergence))
     }
 
-    
+    /// The ray origin for the perspective and orthographic lenses.
+    /// A defocus angle of 0 is a pinhole.
+    fn lens_origin(
+        &self,
+        t: f64,
+        lens: (f64, f64),
+        image_pos: (f64, f64),
+        basis: &Basis,
+    ) -> Option<Point3> {
+        if self.defocus_radius(t) <= 0.0 {
+            Some(self.get_from(t))
+        } else {
+            self.defocus_disk_sample(t, lens, image_pos, basis)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projections_look_forward() {
+        let mut cam = Camera::new(2.0, 64, 24.0, 180.0, 1);
+        cam.look_from(Point3::new(1.0, 2.0, 3.0));
+        cam.look_at(Point3::new(1.0, 2.0, 0.0));
+        let forward = Vec3::new(0.0, 0.0, -1.0);
+
+        for projection in [
+            Projection::Perspective,
+            Projection::Orthographic { scale: 4.0 },
+            Projection::FisheyeEquidistant { fov: 180.0 },
+            Projection::FisheyeEquisolid { fov: 180.0 },
+            Projection::Equirectangular,
+        ] {
+            cam.set_projection(projection.clone());
+            // The center of the 64x32 image is the corner of 4 pixels
+            let ray = cam
+                .generate_ray(31, 15, (0.5, 0.5), 0.0, (0.0, 0.0))
+                .unwrap();
+            let dir = ray.direction().clone().unit_vector();
+
+            assert!((dir - forward.clone()).length() < 1e-9, "{projection:?}");
+        }
+    }
+
+    #[test]
+    fn projection_edges() {
+        let mut cam = Camera::new(2.0, 64, 24.0, 180.0, 1);
+        cam.look_from(Point3::origin());
+        cam.look_at(Point3::new(0.0, 0.0, -1.0));
+
+        // Fisheyes only fill the circle in the middle
+        cam.set_projection(Projection::FisheyeEquidistant { fov: 180.0 });
+        assert!(
+            cam.generate_ray(0, 0, (0.0, 0.0), 0.0, (0.0, 0.0))
+                .is_none()
+        );
+        let side = cam
+            .generate_ray(16, 15, (-0.5, 0.5), 0.0, (0.0, 0.0))
+            .unwrap();
+        assert!(side.direction().z().abs() < 1e-9);
+
+        // Orthographic rays are parallel
+        cam.set_projection(Projection::Orthographic { scale: 4.0 });
+        let corner = cam
+            .generate_ray(0, 0, (-0.5, -0.5), 0.0, (0.0, 0.0))
+            .unwrap();
+        assert_eq!(corner.origin(), &Point3::new(-4.0, 2.0, 0.0));
+        assert_eq!(corner.direction().x(), 0.0);
+
+        // The top row of a panorama looks straight up
+        cam.set_projection(Projection::Equirectangular);
+        let up = cam
+            .generate_ray(20, 0, (0.0, -0.5), 0.0, (0.0, 0.0))
+            .unwrap();
+        assert!((up.direction().clone().unit_vector().y() - 1.0).abs() < 1e-9);
+    }
 }
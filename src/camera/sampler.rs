@@ -0,0 +1,478 @@
+use clap::ValueEnum;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+// The sample dimensions are laid out the same way for every
+// pixel so each one lines up with the same part of the path
+const PIXEL_DIM: u32 = 0;
+const TIME_DIM: u32 = 2;
+const LENS_DIM: u32 = 3;
+const BOUNCE_DIM: u32 = 5;
+/// A 2D direction and a 1D choice for the material of every bounce,
+/// then a 1D choice of light and a 2D point on it, a 1D choice to end
+/// the path and last a 1D number each for the media on the way to the
+/// bounce and on the way to the light
+const DIMS_PER_BOUNCE: u32 = 9;
+const LIGHT_DIM: u32 = 3;
+const ROULETTE_DIM: u32 = 6;
+const MEDIUM_DIM: u32 = 7;
+const SHADOW_DIM: u32 = 8;
+
+/// The first primes, one Halton base per dimension. Dimensions
+/// past this fall back to random numbers, by then a path has
+/// bounced so often that the pattern no longer matters.
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// How the camera picks the numbers for each sample. This covers
+/// the position in the pixel, the shutter time, the lens and the
+/// direction of every bounce.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum SamplingMethod {
+    /// Independent uniform random numbers
+    #[value(alias = "random")]
+    Square,
+    /// Jittered strata, the strata of each dimension are shuffled
+    /// separately so the dimensions do not line up
+    Stratified,
+    /// The Halton sequence with its digits Owen scrambled for
+    /// every pixel
+    Halton,
+    /// Owen scrambled Sobol. The 2D Sobol sequence is padded out to
+    /// every dimension by shuffling the sample order per dimension
+    Sobol,
+    /// The R2 sequence offset by interleaved gradient noise, the error
+    /// between neighbouring pixels looks like blue noise
+    #[value(name = "bluenoise", alias = "blue-noise")]
+    BlueNoise,
+}
+
+impl SamplingMethod {
+    /// Picks a method from a name such as "sobol", this is meant for
+    /// command line flags. Returns None if the name is not a method.
+    pub fn from_name(name: &str) -> Option<SamplingMethod> {
+        SamplingMethod::from_str(name, true).ok()
+    }
+}
+
+/// Hands out the numbers for one sample of one pixel at a time.
+/// Every number is in [0, 1). The numbers only depend on the seed,
+/// the pixel and the sample index so renders can be reproduced.
+pub struct Sampler {
+    method: SamplingMethod,
+    samples_per_pixel: u32,
+    batch_size: u32,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: u32,
+    dimension: u32,
+    bounce: u32,
+    rng: SmallRng,
+}
+
+impl Sampler {
+    /// Makes a sampler for pixels that take samples_per_pixel
+    /// samples. Stratified sampling needs this to size its strata.
+    pub(crate) fn new(method: SamplingMethod, samples_per_pixel: u32, seed: u64) -> Sampler {
+        Sampler {
+            method,
+            samples_per_pixel: samples_per_pixel.max(1),
+            batch_size: samples_per_pixel.max(1),
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+            bounce: 0,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Splits the samples of a pixel into batches that stratified
+    /// sampling stratifies on their own, so a pixel that stops after
+    /// any whole batch still covers every stratum evenly. By default
+    /// all of the samples are one batch.
+    pub(crate) fn set_batch_size(&mut self, batch_size: u32) {
+        self.batch_size = batch_size.clamp(1, self.samples_per_pixel);
+    }
+
+    /// Moves to sample sample_index of pixel (i, j). The random
+    /// numbers restart from a stream of their own for every sample.
+    pub(crate) fn start_pixel_sample(&mut self, i: u32, j: u32, sample_index: u32) {
+        self.pixel = (i, j);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.bounce = 0;
+        self.rng = SmallRng::seed_from_u64(self.pixel_hash(u32::MAX) ^ sample_index as u64);
+    }
+
+    /// Offset from the center of the pixel, each axis is in [-0.5, 0.5)
+    pub(crate) fn pixel_offset(&mut self) -> (f64, f64) {
+        self.dimension = PIXEL_DIM;
+        let (x, y) = self.get_2d();
+
+        (x - 0.5, y - 0.5)
+    }
+
+    /// Where in the shutter interval the sample lands
+    pub(crate) fn time(&mut self) -> f64 {
+        self.dimension = TIME_DIM;
+        self.get_1d()
+    }
+
+    /// Where on the lens the sample passes through
+    pub(crate) fn lens(&mut self) -> (f64, f64) {
+        self.dimension = LENS_DIM;
+        self.get_2d()
+    }
+
+    /// Moves to the dimensions of the next bounce. Materials can take
+    /// a 2D and a 1D number per bounce and stay in step.
+    pub(crate) fn start_bounce(&mut self) {
+        self.dimension = BOUNCE_DIM + self.bounce * DIMS_PER_BOUNCE;
+        self.bounce += 1;
+    }
+
+    /// Moves to the dimensions for sampling a light at the current
+    /// bounce, a 1D number picks the light and a 2D number a point on it
+    pub(crate) fn start_light(&mut self) {
+        self.dimension = self.bounce_start() + LIGHT_DIM;
+    }
+
+    /// The number that decides if the path ends at the current bounce
+    pub(crate) fn roulette(&mut self) -> f64 {
+        self.dimension = self.bounce_start() + ROULETTE_DIM;
+        self.get_1d()
+    }
+
+    /// The number media scatter the ray to the next bounce by, this
+    /// is called before start_bounce for that bounce
+    pub(crate) fn medium(&mut self) -> f64 {
+        self.dimension = BOUNCE_DIM + self.bounce * DIMS_PER_BOUNCE + MEDIUM_DIM;
+        self.get_1d()
+    }
+
+    /// The number media dim the shadow ray to the light sampled at
+    /// the current bounce by
+    pub(crate) fn shadow(&mut self) -> f64 {
+        self.dimension = self.bounce_start() + SHADOW_DIM;
+        self.get_1d()
+    }
+
+    /// The first dimension of the current bounce
+    fn bounce_start(&self) -> u32 {
+        BOUNCE_DIM + self.bounce.saturating_sub(1) * DIMS_PER_BOUNCE
+    }
+
+    pub fn get_1d(&mut self) -> f64 {
+        let dim = self.dimension;
+        self.dimension += 1;
+
+        match self.method {
+            SamplingMethod::Square => self.rng.random(),
+            SamplingMethod::Stratified => {
+                let (stratum, strata, _) = self.stratum(dim);
+
+                (stratum as f64 + self.rng.random::<f64>()) / strata as f64
+            }
+            SamplingMethod::Halton => self.halton(dim),
+            SamplingMethod::Sobol => sobol_owen(self.sample_index, self.pixel_hash(dim)).0,
+            SamplingMethod::BlueNoise => {
+                let r1 = 0.5 + 0.618_033_988_749_894_9 * self.sample_index as f64;
+                (r1 + self.noise_offset(dim)).fract()
+            }
+        }
+    }
+
+    pub fn get_2d(&mut self) -> (f64, f64) {
+        let dim = self.dimension;
+        self.dimension += 2;
+
+        match self.method {
+            SamplingMethod::Square => (self.rng.random(), self.rng.random()),
+            SamplingMethod::Stratified => {
+                // Correlated multi-jitter, every sample has a row of its
+                // own and the columns of an nx by ny grid are shuffled
+                // so any number of samples covers the whole square
+                let (stratum, strata, seed) = self.stratum(dim);
+                let nx = ((strata as f64).sqrt() as u32).max(1);
+                let ny = strata.div_ceil(nx);
+                let sx = permutation_element(stratum % nx, nx, mix(seed ^ 1) as u32);
+                let sy = permutation_element(stratum / nx, ny, mix(seed ^ 2) as u32);
+                let jx = self.rng.random::<f64>();
+                let jy = self.rng.random::<f64>();
+
+                (
+                    (sx as f64 + (sy as f64 + jx) / ny as f64) / nx as f64,
+                    (stratum as f64 + jy) / strata as f64,
+                )
+            }
+            SamplingMethod::Halton => (self.halton(dim), self.halton(dim + 1)),
+            SamplingMethod::Sobol => sobol_owen(self.sample_index, self.pixel_hash(dim)),
+            SamplingMethod::BlueNoise => {
+                // The plastic number generalizes the golden ratio to 2D
+                let g = 1.324_717_957_244_746;
+                let n = self.sample_index as f64;
+                let x = 0.5 + n / g;
+                let y = 0.5 + n / (g * g);
+
+                (
+                    (x + self.noise_offset(dim)).fract(),
+                    (y + self.noise_offset(dim + 1)).fract(),
+                )
+            }
+        }
+    }
+
+    /// The stratum of the current sample in dimension dim, the
+    /// number of strata in its batch and the seed that shuffled them.
+    /// Only the last batch can be smaller than the batch size.
+    fn stratum(&self, dim: u32) -> (u32, u32, u64) {
+        let batch = self.sample_index / self.batch_size;
+        let first = batch * self.batch_size;
+        let strata = self
+            .batch_size
+            .min(self.samples_per_pixel.saturating_sub(first))
+            .max(1);
+        let seed = self.pixel_hash(dim) ^ mix(batch as u64);
+
+        let stratum =
+            permutation_element((self.sample_index - first) % strata, strata, seed as u32);
+        (stratum, strata, seed)
+    }
+
+    fn halton(&mut self, dim: u32) -> f64 {
+        match PRIMES.get(dim as usize) {
+            Some(&base) => {
+                scrambled_radical_inverse(base, self.sample_index as u64, self.pixel_hash(dim))
+            }
+            None => self.rng.random(),
+        }
+    }
+
+    /// Interleaved gradient noise, shifted for every dimension. The
+    /// seed moves the whole pattern which keeps it blue.
+    fn noise_offset(&self, dim: u32) -> f64 {
+        let x = self.pixel.0 as f64 + 5.588238 * dim as f64;
+        let y = self.pixel.1 as f64 + 5.588238 * dim as f64;
+        let shift = (mix(self.seed ^ dim as u64) >> 11) as f64 / (1u64 << 53) as f64;
+
+        (52.9829189 * (0.06711056 * x + 0.00583715 * y).fract() + shift).fract()
+    }
+
+    fn pixel_hash(&self, dim: u32) -> u64 {
+        let pixel = ((self.pixel.1 as u64) << 32) | self.pixel.0 as u64;
+
+        mix(mix(mix(self.seed) ^ pixel) ^ dim as u64)
+    }
+}
+
+/// The splitmix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Mirrors the digits of index around the decimal point in base.
+/// Every digit is permuted based on the digits before it, which
+/// is Owen scrambling. This keeps going past the last digit of the
+/// index so the trailing zeros are scrambled too.
+fn scrambled_radical_inverse(base: u32, mut index: u64, seed: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits: u64 = 0;
+
+    // Stop once another digit would be below f64 precision
+    while 1.0 - inv_base_m < 1.0 {
+        let digit = (index % base as u64) as u32;
+        index /= base as u64;
+
+        let digit = permutation_element(digit, base, mix(seed ^ reversed_digits) as u32);
+        reversed_digits = reversed_digits * base as u64 + digit as u64;
+        inv_base_m *= inv_base;
+    }
+
+    (inv_base_m * reversed_digits as f64).min(1.0 - f64::EPSILON / 2.0)
+}
+
+/// Kensler's hash based permutation. Returns where i lands in a
+/// random permutation of 0..len chosen by seed.
+fn permutation_element(mut i: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < len {
+            return i.wrapping_add(seed) % len;
+        }
+    }
+}
+
+/// Burley's hash based Owen scrambled Sobol. The index is shuffled
+/// and both coordinates are scrambled using the seed.
+fn sobol_owen(index: u32, seed: u64) -> (f64, f64) {
+    let shuffled = nested_uniform_scramble(index, mix(seed) as u32);
+
+    let x = nested_uniform_scramble(shuffled.reverse_bits(), mix(seed ^ 1) as u32);
+    let y = nested_uniform_scramble(sobol_second_dim(shuffled), mix(seed ^ 2) as u32);
+
+    let scale = 1.0 / (1u64 << 32) as f64;
+    (x as f64 * scale, y as f64 * scale)
+}
+
+/// The second Sobol dimension, its generator matrix is Pascal's
+/// triangle mod 2. The first is just the reversed index bits.
+fn sobol_second_dim(mut index: u32) -> u32 {
+    let mut v = 1 << 31;
+    let mut result = 0;
+
+    while index != 0 {
+        if index & 1 == 1 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+
+    result
+}
+
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_in_unit_interval() {
+        for method in [
+            SamplingMethod::Square,
+            SamplingMethod::Stratified,
+            SamplingMethod::Halton,
+            SamplingMethod::Sobol,
+            SamplingMethod::BlueNoise,
+        ] {
+            let mut sampler = Sampler::new(method.clone(), 16, 7);
+
+            for s in 0..16 {
+                sampler.start_pixel_sample(7, 3, s);
+                let (ox, oy) = sampler.pixel_offset();
+                let mut values = vec![ox + 0.5, oy + 0.5, sampler.time()];
+
+                for _ in 0..50 {
+                    sampler.start_bounce();
+                    let (u, v) = sampler.get_2d();
+                    values.extend([u, v, sampler.get_1d()]);
+                }
+
+                for v in values {
+                    assert!((0.0..1.0).contains(&v), "{method:?} gave {v}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stratified_hits_every_stratum() {
+        let mut sampler = Sampler::new(SamplingMethod::Stratified, 16, 7);
+        let mut strata = [0; 16];
+
+        for s in 0..16 {
+            sampler.start_pixel_sample(2, 9, s);
+            strata[(sampler.time() * 16.0) as usize] += 1;
+        }
+
+        assert!(strata.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn stratified_batches_hit_every_stratum() {
+        let mut sampler = Sampler::new(SamplingMethod::Stratified, 16, 7);
+        sampler.set_batch_size(4);
+
+        for batch in 0..4 {
+            let mut strata = [0; 4];
+            for s in 0..4 {
+                sampler.start_pixel_sample(2, 9, 4 * batch + s);
+                strata[(sampler.time() * 4.0) as usize] += 1;
+            }
+
+            assert_eq!(strata, [1, 1, 1, 1]);
+        }
+    }
+
+    #[test]
+    fn stratified_covers_the_square() {
+        // 10 samples do not make a square grid, they still have to
+        // reach every corner of the square
+        for pixel in 0..50 {
+            let mut sampler = Sampler::new(SamplingMethod::Stratified, 10, 7);
+            let mut rows = [0; 10];
+            let mut columns = [0; 3];
+            let mut quadrants = [0; 4];
+
+            for s in 0..10 {
+                sampler.start_pixel_sample(pixel, 4, s);
+                let (u, v) = sampler.lens();
+                rows[(v * 10.0) as usize] += 1;
+                columns[(u * 3.0) as usize] += 1;
+                quadrants[(u * 2.0) as usize + 2 * (v * 2.0) as usize] += 1;
+            }
+
+            assert_eq!(rows, [1; 10]);
+            assert!(columns.iter().all(|&count| count > 0));
+            assert!(quadrants.iter().all(|&count| count > 0), "{quadrants:?}");
+        }
+    }
+
+    #[test]
+    fn sobol_fills_quadrants() {
+        let mut sampler = Sampler::new(SamplingMethod::Sobol, 4, 7);
+        let mut quadrants = [0; 4];
+
+        for s in 0..4 {
+            sampler.start_pixel_sample(11, 5, s);
+            let (u, v) = sampler.lens();
+            quadrants[(u * 2.0) as usize + 2 * (v * 2.0) as usize] += 1;
+        }
+
+        assert_eq!(quadrants, [1, 1, 1, 1]);
+    }
+}
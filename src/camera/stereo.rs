@@ -0,0 +1,113 @@
+use clap::ValueEnum;
+
+use crate::camera::framebuffer::Framebuffer;
+
+/// How the two eyes of a stereo render are saved
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum StereoLayout {
+    /// One image twice as wide, the left eye on the left
+    #[value(alias = "sbs")]
+    SideBySide,
+    /// One image twice as tall, the left eye on top. VR players
+    /// expect omni-directional stereo panoramas like this.
+    #[value(alias = "ou")]
+    OverUnder,
+    /// Two images with _left and _right added to the file name
+    #[value(name = "separate")]
+    SeparateFiles,
+}
+
+impl StereoLayout {
+    /// Picks a layout from a name such as "side-by-side", this is
+    /// meant for command line flags. Returns None if the name is not
+    /// a layout.
+    pub fn from_name(name: &str) -> Option<StereoLayout> {
+        StereoLayout::from_str(name, true).ok()
+    }
+}
+
+/// The settings of a stereo camera, see `Camera::set_stereo`
+#[derive(Debug, Clone)]
+pub(super) struct Stereo {
+    pub(super) interocular: f64,
+    pub(super) convergence: f64,
+    pub(super) layout: StereoLayout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Eye {
+    Left,
+    Right,
+}
+
+impl Eye {
+    /// Which way along the camera's u basis the eye sits
+    pub(super) fn side(self) -> f64 {
+        match self {
+            Eye::Left => -1.0,
+            Eye::Right => 1.0,
+        }
+    }
+
+    /// What is added to the file name of this eye's image
+    pub(super) fn suffix(self) -> &'static str {
+        match self {
+            Eye::Left => "_left",
+            Eye::Right => "_right",
+        }
+    }
+}
+
+impl Stereo {
+    /// The file each eye is rendered into. With a combined layout
+    /// these only hold the checkpoints.
+    pub(super) fn eye_file_names(&self, fname: &str) -> [(Eye, String); 2] {
+        [Eye::Left, Eye::Right].map(|eye| (eye, eye_file_name(fname, eye)))
+    }
+
+    /// Puts both eyes in one image
+    pub(super) fn combine(&self, left: &Framebuffer, right: &Framebuffer) -> Framebuffer {
+        let (w, h) = (left.width(), left.height());
+
+        let (mut both, x, y) = match self.layout {
+            StereoLayout::OverUnder => (Framebuffer::new(w, 2 * h), 0, h),
+            _ => (Framebuffer::new(2 * w, h), w, 0),
+        };
+        both.paste(left, 0, 0);
+        both.paste(right, x, y);
+
+        both
+    }
+}
+
+/// Adds the eye before the extension, image.png becomes image_left.png
+fn eye_file_name(fname: &str, eye: Eye) -> String {
+    let stem_end = fname
+        .rfind('.')
+        .filter(|&dot| !fname[dot..].contains('/'))
+        .unwrap_or(fname.len());
+
+    format!(
+        "{}{}{}",
+        &fname[..stem_end],
+        eye.suffix(),
+        &fname[stem_end..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eye_names() {
+        assert_eq!(
+            eye_file_name("out/image.png", Eye::Left),
+            "out/image_left.png"
+        );
+        assert_eq!(
+            eye_file_name("./movie/img", Eye::Right),
+            "./movie/img_right"
+        );
+    }
+}
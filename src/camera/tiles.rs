@@ -0,0 +1,235 @@
+/// The order tiles are handed out to the render threads. Tiles
+/// that are close together in the ordering are close together
+/// in the image, which keeps the BVH nodes a thread touches warm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom
+    Scanline,
+    /// Starts at the center of the image and walks outwards
+    Spiral,
+    /// Follows a Hilbert curve over the tile grid
+    Hilbert,
+}
+
+/// A rectangle of pixels that a thread renders as a single job.
+/// The end coordinates are exclusive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    pub fn new(x0: u32, y0: u32, x1: u32, y1: u32) -> Tile {
+        Tile { x0, y0, x1, y1 }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y1 - self.y0
+    }
+
+    /// The number of pixels inside the tile
+    pub fn pixel_count(&self) -> u64 {
+        self.width() as u64 * self.height() as u64
+    }
+}
+
+/// Splits region of an image into tiles of tile_size x tile_size
+/// pixels, the tile grid starts at the corner of the region. Tiles
+/// on the right and bottom edges are clipped to the region. The tiles
+/// are returned in the order they should be rendered.
+pub(super) fn build_tiles(region: &Tile, tile_size: u32, order: &TileOrder) -> Vec<Tile> {
+    let tiles_x = region.width().div_ceil(tile_size);
+    let tiles_y = region.height().div_ceil(tile_size);
+
+    let grid_order = match order {
+        TileOrder::Scanline => scanline_order(tiles_x, tiles_y),
+        TileOrder::Spiral => spiral_order(tiles_x, tiles_y),
+        TileOrder::Hilbert => hilbert_order(tiles_x, tiles_y),
+    };
+
+    grid_order
+        .into_iter()
+        .map(|(tx, ty)| {
+            let x0 = region.x0 + tx * tile_size;
+            let y0 = region.y0 + ty * tile_size;
+            Tile::new(
+                x0,
+                y0,
+                (x0 + tile_size).min(region.x1),
+                (y0 + tile_size).min(region.y1),
+            )
+        })
+        .collect()
+}
+
+fn scanline_order(tiles_x: u32, tiles_y: u32) -> Vec<(u32, u32)> {
+    let mut order = Vec::with_capacity((tiles_x * tiles_y) as usize);
+
+    for ty in 0..tiles_y {
+        for tx in 0..tiles_x {
+            order.push((tx, ty));
+        }
+    }
+
+    order
+}
+
+/// Walks a square spiral around the center tile. Steps that land
+/// outside of the grid are skipped so the spiral keeps growing until
+/// every tile has been visited.
+fn spiral_order(tiles_x: u32, tiles_y: u32) -> Vec<(u32, u32)> {
+    let total = (tiles_x * tiles_y) as usize;
+    let mut order = Vec::with_capacity(total);
+    // An empty grid has no center to start from
+    if total == 0 {
+        return order;
+    }
+
+    let mut x = ((tiles_x - 1) / 2) as i64;
+    let mut y = ((tiles_y - 1) / 2) as i64;
+    // right, down, left, up
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut dir = 0;
+    let mut leg_length = 1;
+
+    let push_if_valid = |x: i64, y: i64, order: &mut Vec<(u32, u32)>| {
+        if x >= 0 && y >= 0 && x < tiles_x as i64 && y < tiles_y as i64 {
+            order.push((x as u32, y as u32));
+        }
+    };
+
+    push_if_valid(x, y, &mut order);
+
+    while order.len() < total {
+        // Each leg length is walked twice before it grows
+        for _ in 0..2 {
+            let (dx, dy) = directions[dir];
+            for _ in 0..leg_length {
+                x += dx;
+                y += dy;
+                push_if_valid(x, y, &mut order);
+            }
+            dir = (dir + 1) % 4;
+        }
+        leg_length += 1;
+    }
+
+    order
+}
+
+/// Orders the tiles by their distance along a Hilbert curve covering
+/// the smallest power of two square that fits the grid.
+fn hilbert_order(tiles_x: u32, tiles_y: u32) -> Vec<(u32, u32)> {
+    let n = tiles_x.max(tiles_y).next_power_of_two();
+
+    let mut order = scanline_order(tiles_x, tiles_y);
+    order.sort_by_key(|&(x, y)| hilbert_index(n, x, y));
+
+    order
+}
+
+/// Converts an (x, y) cell of an n x n grid into its distance along the
+/// Hilbert curve. n must be a power of two.
+fn hilbert_index(n: u32, x: u32, y: u32) -> u64 {
+    let n = n as u64;
+    let mut x = x as u64;
+    let mut y = y as u64;
+    let mut d = 0;
+
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = ((x & s) > 0) as u64;
+        let ry = ((y & s) > 0) as u64;
+        d += s * s * ((3 * rx) ^ ry);
+
+        // Rotate the quadrant so the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covers_image_once(width: u32, height: u32, tile_size: u32, order: TileOrder) {
+        let tiles = build_tiles(&Tile::new(0, 0, width, height), tile_size, &order);
+        let mut seen = vec![0; (width * height) as usize];
+
+        for tile in tiles {
+            for j in tile.y0..tile.y1 {
+                for i in tile.x0..tile.x1 {
+                    seen[(j * width + i) as usize] += 1;
+                }
+            }
+        }
+
+        assert!(
+            seen.iter().all(|&count| count == 1),
+            "{order:?} missed or repeated a pixel"
+        );
+    }
+
+    #[test]
+    fn tile_orders_cover_image() {
+        covers_image_once(100, 37, 16, TileOrder::Scanline);
+        covers_image_once(100, 37, 16, TileOrder::Spiral);
+        covers_image_once(100, 37, 16, TileOrder::Hilbert);
+        covers_image_once(5, 300, 32, TileOrder::Spiral);
+    }
+
+    #[test]
+    fn region_tiles_stay_inside() {
+        let region = Tile::new(10, 5, 50, 20);
+        let tiles = build_tiles(&region, 16, &TileOrder::Hilbert);
+        let covered: u64 = tiles.iter().map(|t| t.pixel_count()).sum();
+
+        assert_eq!(covered, region.pixel_count());
+        assert!(
+            tiles
+                .iter()
+                .all(|t| t.x0 >= 10 && t.y0 >= 5 && t.x1 <= 50 && t.y1 <= 20)
+        );
+    }
+
+    #[test]
+    fn empty_regions_have_no_tiles() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            assert!(build_tiles(&Tile::new(4, 4, 4, 20), 8, &order).is_empty());
+            assert!(build_tiles(&Tile::new(4, 4, 20, 4), 8, &order).is_empty());
+        }
+    }
+
+    #[test]
+    fn spiral_starts_in_center() {
+        let tiles = build_tiles(&Tile::new(0, 0, 96, 96), 32, &TileOrder::Spiral);
+
+        assert_eq!(tiles[0], Tile::new(32, 32, 64, 64));
+    }
+
+    #[test]
+    fn hilbert_steps_are_adjacent() {
+        let tiles = build_tiles(&Tile::new(0, 0, 128, 128), 16, &TileOrder::Hilbert);
+
+        for pair in tiles.windows(2) {
+            let dx = (pair[0].x0 as i64 - pair[1].x0 as i64).abs();
+            let dy = (pair[0].y0 as i64 - pair[1].y0 as i64).abs();
+            assert_eq!(dx + dy, 16);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use clap::{ValueEnum, builder::PossibleValue};
+
+use crate::utils::{Color, Radiance};
+
+/// Operators that compress unbounded radiance into the displayable
+/// 0 to 1 range. They only affect the 8 and 16 bit output formats.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneMapper {
+    /// Clips every channel at 1.0
+    Clamp,
+    /// Maps the luminance L to L / (1 + L), nothing reaches pure white
+    Reinhard,
+    /// Reinhard that maps a luminance of white_point to pure white
+    ExtendedReinhard { white_point: f64 },
+    /// Stephen Hill's fit of the ACES reference rendering transform
+    Aces,
+    /// Troy Sobotka's AgX, very bright colors wash out to white
+    /// instead of shifting hue
+    AgX,
+}
+
+impl ToneMapper {
+    /// Picks an operator from a name such as "aces", this is meant
+    /// for command line flags. Extended Reinhard gets a white point
+    /// of 4.0. Returns None if the name is not an operator.
+    pub fn from_name(name: &str) -> Option<ToneMapper> {
+        ToneMapper::from_str(name, true).ok()
+    }
+}
+
+/// The command line names, extended Reinhard is parsed with a white
+/// point of 4.0
+impl ValueEnum for ToneMapper {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ToneMapper::Clamp,
+            ToneMapper::Reinhard,
+            ToneMapper::ExtendedReinhard { white_point: 4.0 },
+            ToneMapper::Aces,
+            ToneMapper::AgX,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let value = match self {
+            ToneMapper::Clamp => PossibleValue::new("clamp").help("Clips every channel at 1.0"),
+            ToneMapper::Reinhard => {
+                PossibleValue::new("reinhard").help("Maps the luminance L to L / (1 + L)")
+            }
+            ToneMapper::ExtendedReinhard { .. } => PossibleValue::new("extended-reinhard")
+                .help("Reinhard that reaches pure white at a white point"),
+            ToneMapper::Aces => PossibleValue::new("aces").help("The ACES filmic curve"),
+            ToneMapper::AgX => {
+                PossibleValue::new("agx").help("AgX, very bright colors wash out to white")
+            }
+        };
+
+        Some(value)
+    }
+}
+
+/// How the tone mapped values are encoded in the output file
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferFunction {
+    /// The piecewise sRGB curve
+    Srgb,
+    /// A pure power curve, 2.2 is the common display gamma
+    Gamma(f64),
+    /// No encoding at all
+    Linear,
+}
+
+/// Everything that happens between the radiance in the framebuffer
+/// and the bytes in a display image: exposure, then the tone mapper,
+/// then the transfer function.
+///
+/// The default is a clamp with gamma 2 which matches how Color is
+/// displayed.
+#[derive(Debug, Clone)]
+pub struct ToneMapping {
+    mapper: ToneMapper,
+    exposure: f64,
+    transfer: TransferFunction,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping {
+            mapper: ToneMapper::Clamp,
+            exposure: 0.0,
+            transfer: TransferFunction::Gamma(2.0),
+        }
+    }
+}
+
+impl ToneMapping {
+    pub fn new(mapper: ToneMapper, exposure: f64, transfer: TransferFunction) -> ToneMapping {
+        ToneMapping {
+            mapper,
+            exposure,
+            transfer,
+        }
+    }
+
+    pub fn mapper(&self) -> &ToneMapper {
+        &self.mapper
+    }
+
+    /// The exposure in stops, every stop doubles the light
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    pub fn transfer(&self) -> &TransferFunction {
+        &self.transfer
+    }
+
+    pub(super) fn set_mapper(&mut self, mapper: ToneMapper) {
+        self.mapper = mapper;
+    }
+
+    pub(super) fn set_exposure(&mut self, stops: f64) {
+        self.exposure = stops;
+    }
+
+    pub(super) fn set_transfer(&mut self, transfer: TransferFunction) {
+        self.transfer = transfer;
+    }
+
+    /// Scales the radiance by the exposure. This is all that is
+    /// applied to the float output formats.
+    pub fn expose(&self, radiance: &Radiance) -> Radiance {
+        radiance.clone() * 2f64.powf(self.exposure)
+    }
+
+    /// Exposes and tone maps the radiance. The result is still
+    /// linear, it has not been encoded for display yet.
+    pub fn tone_map(&self, radiance: &Radiance) -> Color {
+        let exposed = self.expose(radiance);
+
+        match self.mapper {
+            ToneMapper::Clamp => exposed.clamp_to_color(),
+            ToneMapper::Reinhard => reinhard(&exposed, f64::INFINITY),
+            ToneMapper::ExtendedReinhard { white_point } => reinhard(&exposed, white_point),
+            ToneMapper::Aces => aces(&exposed),
+            ToneMapper::AgX => agx(&exposed),
+        }
+    }
+
+    /// Runs the whole pipeline and returns the encoded channels,
+    /// each between 0 and 1
+    pub fn display_channels(&self, radiance: &Radiance) -> (f64, f64, f64) {
+        let color = self.tone_map(radiance);
+
+        (
+            self.encode(color.r()),
+            self.encode(color.g()),
+            self.encode(color.b()),
+        )
+    }
+
+    fn encode(&self, linear: f64) -> f64 {
+        match self.transfer {
+            TransferFunction::Srgb => {
+                if linear <= 0.0031308 {
+                    12.92 * linear
+                } else {
+                    1.055 * linear.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            TransferFunction::Gamma(gamma) => linear.powf(1.0 / gamma),
+            TransferFunction::Linear => linear,
+        }
+    }
+}
+
+/// Luminance based Reinhard so the hue is kept. An infinite
+/// white point is the plain operator.
+fn reinhard(radiance: &Radiance, white_point: f64) -> Color {
+    let l = radiance.luminance();
+    if l <= 0.0 {
+        return Color::black();
+    }
+
+    let mapped = l * (1.0 + l / (white_point * white_point)) / (1.0 + l);
+
+    (radiance.clone() * (mapped / l)).clamp_to_color()
+}
+
+fn aces(radiance: &Radiance) -> Color {
+    const INPUT: [[f64; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    const OUTPUT: [[f64; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+
+    let fit =
+        |v: f64| (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.4329510) + 0.238081);
+
+    let v = mat_mul(&INPUT, [radiance.r(), radiance.g(), radiance.b()]);
+    let v = mat_mul(&OUTPUT, v.map(fit));
+
+    Radiance::new(v[0], v[1], v[2]).clamp_to_color()
+}
+
+fn agx(radiance: &Radiance) -> Color {
+    const INSET: [[f64; 3]; 3] = [
+        [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+        [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+        [0.0423756549057051, 0.0784336, 0.879142973793104],
+    ];
+    const OUTSET: [[f64; 3]; 3] = [
+        [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+        [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+        [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+    ];
+    const MIN_EV: f64 = -12.47393;
+    const MAX_EV: f64 = 4.026069;
+
+    // Polynomial fit of the AgX base contrast curve
+    let contrast = |x: f64| {
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+
+    let v = mat_mul(&INSET, [radiance.r(), radiance.g(), radiance.b()]);
+    let v = v.map(|c| {
+        let ev = c.max(1e-10).log2().clamp(MIN_EV, MAX_EV);
+        contrast((ev - MIN_EV) / (MAX_EV - MIN_EV))
+    });
+    // AgX ends in display space, undo the 2.2 gamma so the
+    // transfer function can be applied like the other operators
+    let v = mat_mul(&OUTSET, v).map(|c| c.max(0.0).powf(2.2));
+
+    Radiance::new(v[0], v[1], v[2]).clamp_to_color()
+}
+
+fn mat_mul(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_color_display() {
+        let tm = ToneMapping::default();
+        let c = Color::new(0.529, 0.616, 0.730);
+
+        let (r, g, b) = tm.display_channels(&Radiance::from(c.clone()));
+
+        assert_eq!(r, Color::linear_to_gamma(c.r()));
+        assert_eq!(g, Color::linear_to_gamma(c.g()));
+        assert_eq!(b, Color::linear_to_gamma(c.b()));
+    }
+
+    #[test]
+    fn reinhard_curves() {
+        let grey = Radiance::new(1.0, 1.0, 1.0);
+        let reinhard = ToneMapping::new(ToneMapper::Reinhard, 0.0, TransferFunction::Linear);
+        let extended = ToneMapping::new(
+            ToneMapper::ExtendedReinhard { white_point: 4.0 },
+            0.0,
+            TransferFunction::Linear,
+        );
+
+        assert!((reinhard.tone_map(&grey).r() - 0.5).abs() < 1e-9);
+        assert!((extended.tone_map(&(grey * 4.0)).r() - 1.0).abs() < 1e-9);
+        assert_eq!(
+            ToneMapper::from_name("Extended-Reinhard"),
+            Some(ToneMapper::ExtendedReinhard { white_point: 4.0 })
+        );
+    }
+
+    #[test]
+    fn operators_handle_bright_light() {
+        let sun = Radiance::new(5000.0, 4000.0, 3000.0);
+
+        for mapper in [
+            ToneMapper::Clamp,
+            ToneMapper::Reinhard,
+            ToneMapper::ExtendedReinhard { white_point: 10.0 },
+            ToneMapper::Aces,
+            ToneMapper::AgX,
+        ] {
+            let tm = ToneMapping::new(mapper, 2.0, TransferFunction::Srgb);
+            let (r, g, b) = tm.display_channels(&sun);
+
+            for c in [r, g, b] {
+                assert!((0.0..=1.0).contains(&c), "{:?} gave {c}", tm.mapper());
+            }
+        }
+    }
+}
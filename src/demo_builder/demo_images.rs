@@ -1,15 +1,24 @@
 use std::sync::Arc;
 
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 
 use crate::{
-    materials::{Materials, dielectric::Dielectric, lambertian::Lambertian, metal::Metal},
-    objects::{Hittables, sphere::Sphere},
+    materials::{
+        Materials, dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+        metal::Metal,
+    },
+    objects::{
+        Hittables, constant_medium::ConstantMedium, hitlist::HitList, sphere::Sphere,
+        triangle::Triangle,
+    },
     scene::Scene,
     textures::{Textures, checker_texture::CheckerTexture, image_texture::ImageTexture},
-    utils::{Color, Point3},
+    utils::{Color, Point3, Radiance},
 };
 
+/// The seed the book 1 scene places its small spheres with
+const BOOK1_LAYOUT_SEED: u64 = 1;
+
 /// Here is a function that generates the demo scene from the end of book 1
 pub fn book1_end_scene(threads: usize) -> Scene {
     let mut b1_scene = Scene::new_image(16.0 / 9.0, 400, 24, 180.0, threads);
@@ -41,8 +50,8 @@ pub fn book1_end_scene(threads: usize) -> Scene {
         "ground",
     );
 
-    // rng to pick material
-    let mut rng = rand::rng();
+    // rng to pick material, seeded so the layout is the same every run
+    let mut rng = StdRng::seed_from_u64(BOOK1_LAYOUT_SEED);
     let mut counter = 0;
 
     for a in -11..11 {
@@ -57,7 +66,8 @@ pub fn book1_end_scene(threads: usize) -> Scene {
             if (center.clone() - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 if choose_mat < 0.8 {
                     // diffuse
-                    let albedo = Color::random_color() * Color::random_color();
+                    let albedo =
+                        Color::random_color_from(&mut rng) * Color::random_color_from(&mut rng);
                     let sphere_material =
                         Materials::Lambertian(Lambertian::new_from_color(albedo, 1.0));
                     b1_scene.add_element(
@@ -66,7 +76,7 @@ pub fn book1_end_scene(threads: usize) -> Scene {
                     );
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = Color::random_color_range(0.5, 1.0);
+                    let albedo = Color::random_color_range_from(&mut rng, 0.5, 1.0);
                     let fuzz = rng.random_range(0.0..0.5);
                     let sphere_material = Materials::Metal(Metal::new(albedo, fuzz));
                     b1_scene.add_element(
@@ -240,3 +250,237 @@ pub fn garden_skybox(threads: usize) -> Scene {
 
     garden
 }
+
+/// The Cornell box, a room lit only by the light in its ceiling
+pub fn cornell_box(threads: usize) -> Scene {
+    let mut cornell = cornell_room(threads);
+    let white = Materials::Lambertian(Lambertian::new_from_color(
+        Color::new(0.73, 0.73, 0.73),
+        1.0,
+    ));
+
+    add_box(
+        &mut cornell,
+        Point3::new(165.0, 330.0, 165.0),
+        15.0,
+        Point3::new(265.0, 0.0, 295.0),
+        white.clone(),
+        "tall_box",
+    );
+    add_box(
+        &mut cornell,
+        Point3::new(165.0, 165.0, 165.0),
+        -18.0,
+        Point3::new(130.0, 0.0, 65.0),
+        white,
+        "short_box",
+    );
+
+    cornell
+}
+
+/// The walls and light of the Cornell box with nothing inside
+fn cornell_room(threads: usize) -> Scene {
+    let mut cornell = Scene::new_image(1.0, 600, 24, 180.0, threads);
+
+    cornell.scene_cam.set_samples(200);
+    cornell.scene_cam.set_max_depth(50);
+
+    cornell
+        .scene_cam
+        .look_from(Point3::new(278.0, 278.0, -800.0));
+    cornell.scene_cam.look_at(Point3::new(278.0, 278.0, 0.0));
+
+    cornell.scene_cam.set_vfov(40.0);
+
+    cornell.load_solid_skybox(Radiance::black());
+
+    let red = Materials::Lambertian(Lambertian::new_from_color(
+        Color::new(0.65, 0.05, 0.05),
+        1.0,
+    ));
+    let white = Materials::Lambertian(Lambertian::new_from_color(
+        Color::new(0.73, 0.73, 0.73),
+        1.0,
+    ));
+    let green = Materials::Lambertian(Lambertian::new_from_color(
+        Color::new(0.12, 0.45, 0.15),
+        1.0,
+    ));
+    let light = Materials::DiffuseLight(DiffuseLight::new_from_color(Color::white(), 15.0));
+
+    let corner = Point3::new(555.0, 0.0, 0.0);
+    let up = Point3::new(0.0, 555.0, 0.0);
+    let across = Point3::new(-555.0, 0.0, 0.0);
+    let deep = Point3::new(0.0, 0.0, 555.0);
+
+    add_quad(
+        &mut cornell,
+        corner,
+        up.clone(),
+        deep.clone(),
+        green,
+        "left_wall",
+    );
+    add_quad(
+        &mut cornell,
+        Point3::origin(),
+        up.clone(),
+        deep.clone(),
+        red,
+        "right_wall",
+    );
+    add_quad(
+        &mut cornell,
+        Point3::new(343.0, 554.0, 332.0),
+        Point3::new(-130.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -105.0),
+        light,
+        "light",
+    );
+    add_quad(
+        &mut cornell,
+        Point3::origin(),
+        Point3::new(555.0, 0.0, 0.0),
+        deep.clone(),
+        white.clone(),
+        "floor",
+    );
+    add_quad(
+        &mut cornell,
+        Point3::new(555.0, 555.0, 555.0),
+        across.clone(),
+        Point3::new(0.0, 0.0, -555.0),
+        white.clone(),
+        "ceiling",
+    );
+    add_quad(
+        &mut cornell,
+        Point3::new(0.0, 0.0, 555.0),
+        Point3::new(555.0, 0.0, 0.0),
+        up,
+        white,
+        "back_wall",
+    );
+
+    cornell
+}
+
+/// The Cornell box with its two boxes filled with smoke and a thin
+/// haze in the room
+pub fn cornell_smoke(threads: usize) -> Scene {
+    let mut cornell = cornell_room(threads);
+
+    let unseen = Materials::Lambertian(Lambertian::new_from_color(Color::black(), 1.0));
+    let smoke = |size: Point3, angle: f64, position: Point3| {
+        let triangles = facing_out(box_sides(size, angle, position))
+            .into_iter()
+            .flat_map(|(q, u, v)| quad(q, u, v, unseen.clone()))
+            .map(Hittables::Triangle)
+            .collect();
+
+        Hittables::HitList(HitList::new(triangles))
+    };
+
+    let dark = ConstantMedium::new(
+        smoke(
+            Point3::new(165.0, 330.0, 165.0),
+            15.0,
+            Point3::new(265.0, 0.0, 295.0),
+        ),
+        0.01,
+        Color::new(0.2, 0.2, 0.2),
+        0.0,
+    );
+    let light = ConstantMedium::new(
+        smoke(
+            Point3::new(165.0, 165.0, 165.0),
+            -18.0,
+            Point3::new(130.0, 0.0, 65.0),
+        ),
+        0.01,
+        Color::white(),
+        0.6,
+    );
+    cornell.add_element(Hittables::ConstantMedium(dark), "dark_smoke");
+    cornell.add_element(Hittables::ConstantMedium(light), "light_smoke");
+
+    cornell.set_fog(0.0005, Color::white(), 0.3, 2000.0);
+
+    cornell
+}
+
+/// Adds the parallelogram with a corner at q and sides u and v as
+/// two triangles named {alias}_0 and {alias}_1
+fn add_quad(scene: &mut Scene, q: Point3, u: Point3, v: Point3, mat: Materials, alias: &str) {
+    for (k, triangle) in quad(q, u, v, mat).into_iter().enumerate() {
+        scene.add_element(Hittables::Triangle(triangle), &format!("{alias}_{k}"));
+    }
+}
+
+/// The parallelogram with a corner at q and sides u and v as two
+/// triangles facing u cross v
+fn quad(q: Point3, u: Point3, v: Point3, mat: Materials) -> [Triangle; 2] {
+    let far = q.clone() + u.clone() + v.clone();
+
+    [
+        Triangle::new(q.clone(), q.clone() + u, far.clone(), mat.clone()),
+        Triangle::new(q.clone(), far, q + v, mat),
+    ]
+}
+
+/// Adds a box of size with its corner at the origin, turned around
+/// the y axis by angle degrees and then moved to position
+fn add_box(
+    scene: &mut Scene,
+    size: Point3,
+    angle: f64,
+    position: Point3,
+    mat: Materials,
+    alias: &str,
+) {
+    for (k, (q, u, v)) in box_sides(size, angle, position).into_iter().enumerate() {
+        add_quad(scene, q, u, v, mat.clone(), &format!("{alias}_{k}"));
+    }
+}
+
+/// The corner and sides of each face of a box placed like add_box
+/// does
+fn box_sides(size: Point3, angle: f64, position: Point3) -> [(Point3, Point3, Point3); 6] {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let turn =
+        |p: Point3| Point3::new(cos * p.x() + sin * p.z(), p.y(), -sin * p.x() + cos * p.z());
+
+    let dx = turn(Point3::new(size.x(), 0.0, 0.0));
+    let dy = turn(Point3::new(0.0, size.y(), 0.0));
+    let dz = turn(Point3::new(0.0, 0.0, size.z()));
+    let near = position;
+    let far = near.clone() + dx.clone() + dy.clone() + dz.clone();
+
+    [
+        (near.clone(), dx.clone(), dy.clone()),
+        (near.clone(), dz.clone(), dy.clone()),
+        (near, dx.clone(), dz.clone()),
+        (far.clone(), -dx.clone(), -dy.clone()),
+        (far.clone(), -dz.clone(), -dy),
+        (far, -dx, -dz),
+    ]
+}
+
+/// Turns the faces of a box so every one points out of it, a medium
+/// needs this to tell where it starts and ends
+fn facing_out(sides: [(Point3, Point3, Point3); 6]) -> [(Point3, Point3, Point3); 6] {
+    let center = sides
+        .iter()
+        .map(|(q, u, v)| q.clone() + (u.clone() + v.clone()) / 2.0)
+        .fold(Point3::origin(), |sum, c| sum + c)
+        / 6.0;
+
+    sides.map(|(q, u, v)| {
+        if u.cross(&v).dot(&(q.clone() - center.clone())) < 0.0 {
+            (q, v, u)
+        } else {
+            (q, u, v)
+        }
+    })
+}
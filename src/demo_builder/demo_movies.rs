@@ -1,12 +1,17 @@
 use std::sync::Arc;
 
 use crate::{
+    asset_loader::vol_loader,
+    lights::{
+        Lights,
+        punctual::{SpotLight, SunLight},
+    },
     materials::{Materials, lambertian::Lambertian, metal::Metal},
-    objects::{Hittables, sphere::Sphere},
+    objects::{Hittables, sphere::Sphere, voxel_volume::VoxelVolume},
     scene::Scene,
     textures::{Textures, checker_texture::CheckerTexture},
     timeline::{InterpolationType, TransformSpace},
-    utils::{Color, Point3},
+    utils::{Color, Point3, Radiance, Vec3},
 };
 
 pub fn first_movie(threads: usize, frame_rate: usize, duration: f64) -> Scene {
@@ -126,3 +131,126 @@ pub fn moving_teapot(threads: usize, frame_rate: usize, duration: f64) -> Scene
 
     teapot_scene
 }
+
+/// A sun crosses the sky over the whole movie while a spot light
+/// picks out the ball in the middle
+pub fn sunset(threads: usize, frame_rate: usize, duration: f64) -> Scene {
+    let mut sunset = Scene::new_movie(16.0 / 9.0, 400, frame_rate, 180.0, threads, duration);
+
+    sunset.scene_cam.set_samples(50);
+    sunset.scene_cam.set_max_depth(5);
+
+    sunset.scene_cam.look_from(Point3::new(0.0, 3.0, -12.0));
+    sunset.scene_cam.look_at(Point3::new(0.0, 1.0, 0.0));
+
+    sunset.scene_cam.set_vfov(35.0);
+
+    sunset.load_solid_skybox(Radiance::new(0.05, 0.07, 0.12));
+
+    let ground = Materials::Lambertian(Lambertian::new_from_color(Color::new(0.6, 0.6, 0.55), 1.0));
+    sunset.add_element(
+        Hittables::Sphere(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground)),
+        "ground",
+    );
+
+    let balls = [
+        (
+            Point3::new(-3.0, 1.0, 1.0),
+            Color::new(0.7, 0.2, 0.2),
+            "red_ball",
+        ),
+        (
+            Point3::new(0.0, 1.0, 0.0),
+            Color::new(0.8, 0.8, 0.8),
+            "white_ball",
+        ),
+        (
+            Point3::new(3.0, 1.0, 1.0),
+            Color::new(0.2, 0.3, 0.7),
+            "blue_ball",
+        ),
+    ];
+    for (center, color, alias) in balls {
+        let mat = Materials::Lambertian(Lambertian::new_from_color(color, 1.0));
+        sunset.add_element(Hittables::Sphere(Sphere::new(center, 1.0, mat)), alias);
+    }
+
+    // The real sun is about half a degree across
+    let sun = SunLight::new(Vec3::new(1.0, 0.3, 0.5), Radiance::new(3.0, 2.7, 2.2), 0.53);
+    sunset.add_light(Lights::Sun(sun), "sun");
+
+    let spot = SpotLight::new(
+        Point3::new(0.0, 6.0, -3.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Radiance::new(40.0, 36.0, 30.0),
+        15.0,
+        25.0,
+        2.0,
+    );
+    sunset.add_light(Lights::Spot(spot), "spot");
+
+    // Up over the top and down on the other side
+    sunset.translate_point(
+        Point3::new(0.0, 1.0, 0.3),
+        duration / 2.0,
+        InterpolationType::LERP,
+        TransformSpace::World,
+        "sun",
+    );
+    sunset.translate_point(
+        Point3::new(-1.0, 0.05, 0.5),
+        duration,
+        InterpolationType::LERP,
+        TransformSpace::World,
+        "sun",
+    );
+
+    sunset
+}
+
+/// A fireball loaded from assets/fireball.vol swells up off of the
+/// ground and drifts upwards as it burns out
+pub fn fireball(threads: usize, frame_rate: usize, duration: f64) -> Scene {
+    let mut fireball = Scene::new_movie(16.0 / 9.0, 400, frame_rate, 180.0, threads, duration);
+
+    fireball.scene_cam.set_samples(100);
+    fireball.scene_cam.set_max_depth(10);
+
+    fireball.scene_cam.look_from(Point3::new(0.0, 2.0, -10.0));
+    fireball.scene_cam.look_at(Point3::new(0.0, 1.8, 0.0));
+
+    fireball.scene_cam.set_vfov(40.0);
+
+    fireball.load_solid_skybox(Radiance::new(0.02, 0.02, 0.04));
+
+    let ground = Materials::Lambertian(Lambertian::new_from_color(Color::new(0.5, 0.45, 0.4), 1.0));
+    fireball.add_element(
+        Hittables::Sphere(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground)),
+        "ground",
+    );
+
+    let grid = Arc::new(vol_loader::load_vol("fireball.vol"));
+    let mut ball = VoxelVolume::new(
+        grid,
+        Point3::new(-2.0, 0.0, -2.0),
+        Point3::new(2.0, 4.0, 2.0),
+        Color::new(0.4, 0.4, 0.4),
+        0.3,
+    );
+    ball.set_emission_scale(2.0);
+    fireball.add_element(Hittables::VoxelVolume(ball), "fireball");
+
+    // The corners scale about the origin, so the ball grows up out of
+    // the ground while it rises
+    fireball.scale_all_uniform(0.75, 0.0, InterpolationType::NERP, "fireball");
+    fireball.scale_all_uniform(1.5, duration, InterpolationType::LERP, "fireball");
+    fireball.translate_y(
+        1.5,
+        duration,
+        InterpolationType::LERP,
+        TransformSpace::World,
+        "fireball",
+    );
+
+    fireball
+}
@@ -1,9 +1,10 @@
 mod asset_loader;
-mod camera;
+mod lights;
 mod materials;
 mod objects;
 mod textures;
 
+pub mod camera;
 pub mod demo_builder;
 pub mod scene;
 pub mod timeline;
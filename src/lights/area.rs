@@ -0,0 +1,118 @@
+use std::f64::consts::PI;
+
+use crate::{
+    camera::Ray,
+    lights::{Light, LightSample, perpendicular},
+    materials::Materials,
+    objects::{HitRecord, Hittable, sphere::Sphere, triangle::Triangle},
+    utils::{Interval, Point3, Vec3},
+};
+
+/// A shape with an emissive material. Spheres are sampled over the
+/// cone of directions they cover and triangles evenly over their area.
+#[derive(Debug, Clone)]
+pub enum AreaLight {
+    Sphere(Sphere),
+    Triangle(Triangle),
+}
+
+impl AreaLight {
+    fn material(&self) -> &Materials {
+        match self {
+            AreaLight::Sphere(s) => s.material(),
+            AreaLight::Triangle(t) => t.material(),
+        }
+    }
+
+    /// Where r first hits the light. The interval matches the one
+    /// the camera's rays use.
+    fn hit(&self, r: &Ray) -> Option<HitRecord> {
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        match self {
+            AreaLight::Sphere(s) => s.hit(r, &ray_t),
+            AreaLight::Triangle(t) => t.hit(r, &ray_t),
+        }
+    }
+
+    /// The density of picking the unit length direction of r, which
+    /// hits the light at rec
+    fn density(&self, r: &Ray, rec: &HitRecord) -> f64 {
+        match self {
+            AreaLight::Sphere(s) => match cone(s, r.origin(), r.time()) {
+                Some((_, cos_max)) => 1.0 / (2.0 * PI * (1.0 - cos_max)),
+                None => 0.0,
+            },
+            AreaLight::Triangle(t) => {
+                let [a, b, c] = t.vertices(r.time());
+                let area = 0.5 * (b - a.clone()).cross(&(c - a)).length();
+                let cos = rec.normal().dot(r.direction()).abs();
+
+                if area * cos > 0.0 {
+                    rec.t() * rec.t() / (cos * area)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+impl Light for AreaLight {
+    fn sample(&self, origin: &Point3, time: f64, u: (f64, f64)) -> Option<LightSample> {
+        let direction = match self {
+            AreaLight::Sphere(s) => {
+                let (w, cos_max) = cone(s, origin, time)?;
+                let cos_theta = 1.0 - u.0 * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * u.1;
+
+                let (a, b) = perpendicular(&w);
+                (sin_theta * phi.cos()) * a + (sin_theta * phi.sin()) * b + cos_theta * w
+            }
+            AreaLight::Triangle(t) => {
+                let [a, b, c] = t.vertices(time);
+                let s = u.0.sqrt();
+                let p = (1.0 - s) * a + (s * (1.0 - u.1)) * b + (s * u.1) * c;
+
+                (p - origin.clone()).unit_vector()
+            }
+        };
+
+        let r = Ray::new_at_time(origin.clone(), direction, time);
+        let rec = self.hit(&r)?;
+        let pdf = self.density(&r, &rec);
+        if !(pdf > 0.0 && pdf.is_finite()) {
+            return None;
+        }
+
+        Some(LightSample {
+            radiance: self.material().emitted(&r, &rec),
+            direction: r.direction().clone(),
+            distance: rec.t(),
+            pdf,
+            delta: false,
+        })
+    }
+
+    fn pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> Option<(f64, f64)> {
+        let r = Ray::new_at_time(origin.clone(), direction.clone(), time);
+        let rec = self.hit(&r)?;
+
+        Some((self.density(&r, &rec), rec.t()))
+    }
+}
+
+/// The direction to the center of a sphere and the cosine of the
+/// widest angle it covers, seen from origin. None from inside it.
+fn cone(s: &Sphere, origin: &Point3, time: f64) -> Option<(Vec3, f64)> {
+    let (center, radius) = s.center_and_radius(time);
+    let to_center = center - origin.clone();
+    let dist_squared = to_center.length_squared();
+    if dist_squared <= radius * radius {
+        return None;
+    }
+
+    let cos_max = (1.0 - radius * radius / dist_squared).sqrt();
+    Some((to_center.unit_vector(), cos_max))
+}
@@ -0,0 +1,103 @@
+use std::f64::consts::PI;
+
+use crate::{
+    lights::{Light, LightSample},
+    scene::Skybox,
+    utils::{Distribution2D, Point3, Vec3},
+};
+
+/// The sky as a light. Image skies are sampled in proportion to how
+/// bright each pixel is, the other skies evenly in every direction.
+#[derive(Debug, Clone)]
+pub struct EnvironmentLight {
+    sky: Skybox,
+    /// Picks the pixels of an image sky
+    distribution: Option<Distribution2D>,
+}
+
+impl EnvironmentLight {
+    /// Returns None for skies that give off no light
+    pub fn new(sky: &Skybox) -> Option<EnvironmentLight> {
+        let distribution = match sky {
+            Skybox::Spherical(sky_image) => {
+                let image = sky_image.image();
+                let (width, height) = (image.width(), image.height());
+                let weights = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (x, y)))
+                    .map(|(x, y)| {
+                        // Rows near the poles cover less of the sky
+                        let latitude = (0.5 - (y as f64 + 0.5) / height as f64) * PI;
+                        image.pixel_radiance(x, y).luminance().max(0.0) * latitude.cos()
+                    })
+                    .collect();
+
+                Some(Distribution2D::new(width, height, weights)?)
+            }
+            Skybox::Solid(radiance) if radiance.r().max(radiance.g()).max(radiance.b()) <= 0.0 => {
+                return None;
+            }
+            _ => None,
+        };
+
+        Some(EnvironmentLight {
+            sky: sky.clone(),
+            distribution,
+        })
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _origin: &Point3, _time: f64, u: (f64, f64)) -> Option<LightSample> {
+        let (direction, pdf) = match &self.distribution {
+            Some(distribution) => {
+                let (x, y) = distribution.sample(u);
+                let (width, height) = (distribution.width() as f64, distribution.height() as f64);
+
+                // The same layout `Skybox::radiance` reads the image with
+                let longitude = (x / width - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - y / height) * PI;
+                if latitude.cos() <= 0.0 {
+                    return None;
+                }
+
+                let direction = Vec3::new(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    latitude.cos() * longitude.cos(),
+                );
+                let density = distribution.density(x as usize, y as usize);
+
+                (direction, density / (2.0 * PI * PI * latitude.cos()))
+            }
+            None => (Vec3::unit_vector_from_sample(u), 1.0 / (4.0 * PI)),
+        };
+
+        Some(LightSample {
+            radiance: self.sky.radiance(&direction),
+            direction,
+            distance: f64::INFINITY,
+            pdf,
+            delta: false,
+        })
+    }
+
+    fn pdf(&self, _origin: &Point3, direction: &Vec3, _time: f64) -> Option<(f64, f64)> {
+        let Some(distribution) = &self.distribution else {
+            return Some((1.0 / (4.0 * PI), f64::INFINITY));
+        };
+
+        let longitude = direction.x().atan2(direction.z());
+        let latitude = direction.y().clamp(-1.0, 1.0).asin();
+        let x = (longitude / (2.0 * PI) + 0.5) * distribution.width() as f64;
+        let y = (0.5 - latitude / PI) * distribution.height() as f64;
+
+        let density = distribution.density(x as usize, y as usize);
+        let pdf = if latitude.cos() > 0.0 {
+            density / (2.0 * PI * PI * latitude.cos())
+        } else {
+            0.0
+        };
+
+        Some((pdf, f64::INFINITY))
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    camera::Ray,
+    lights::{
+        area::AreaLight,
+        environment::EnvironmentLight,
+        punctual::{PointLight, SpotLight, SunLight},
+    },
+    objects::Hittables,
+    scene::Skybox,
+    timeline::TransformTimeline,
+    utils::{Point3, Radiance, Vec3},
+};
+
+pub mod area;
+pub mod environment;
+pub mod punctual;
+
+/// A direction towards a light picked by `Light::sample`
+#[derive(Debug, Clone)]
+pub struct LightSample {
+    /// Unit length
+    pub direction: Vec3,
+    /// How far away the light is, infinite for the sky
+    pub distance: f64,
+    /// The light arriving from direction
+    pub radiance: Radiance,
+    /// The density direction was picked with, per unit solid angle
+    pub pdf: f64,
+    /// Bounces never run into the light so the sample counts in full.
+    /// Point, spot and sun lights are like this.
+    pub delta: bool,
+}
+
+/// A wrapper for the lights that can be sampled directly, this
+/// handles dispatching calls to individual lights. Area and
+/// environment lights are found by the scene, the others are added
+/// with `Scene::add_light`.
+#[derive(Debug, Clone)]
+pub enum Lights {
+    Area(AreaLight),
+    Environment(EnvironmentLight),
+    Point(PointLight),
+    Spot(SpotLight),
+    Sun(SunLight),
+}
+
+impl Lights {
+    pub fn sample(&self, origin: &Point3, time: f64, u: (f64, f64)) -> Option<LightSample> {
+        match self {
+            Lights::Area(a) => a.sample(origin, time, u),
+            Lights::Environment(e) => e.sample(origin, time, u),
+            Lights::Point(p) => p.sample(origin, time, u),
+            Lights::Spot(s) => s.sample(origin, time, u),
+            Lights::Sun(s) => s.sample(origin, time, u),
+        }
+    }
+
+    pub fn pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> Option<(f64, f64)> {
+        match self {
+            Lights::Area(a) => a.pdf(origin, direction, time),
+            Lights::Environment(e) => e.pdf(origin, direction, time),
+            Lights::Point(p) => p.pdf(origin, direction, time),
+            Lights::Spot(s) => s.pdf(origin, direction, time),
+            Lights::Sun(s) => s.pdf(origin, direction, time),
+        }
+    }
+
+    /// The scene id of a light added with `Scene::add_light`
+    pub(crate) fn id(&self) -> Option<usize> {
+        match self {
+            Lights::Point(p) => Some(p.id),
+            Lights::Spot(s) => Some(s.id),
+            Lights::Sun(s) => Some(s.id),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set_id(&mut self, id: usize) {
+        match self {
+            Lights::Point(p) => p.id = id,
+            Lights::Spot(s) => s.id = id,
+            Lights::Sun(s) => s.id = id,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn hidden(&self) -> bool {
+        match self {
+            Lights::Point(p) => p.hide,
+            Lights::Spot(s) => s.hide,
+            Lights::Sun(s) => s.hide,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_hidden(&mut self, hide: bool) {
+        match self {
+            Lights::Point(p) => p.hide = hide,
+            Lights::Spot(s) => s.hide = hide,
+            Lights::Sun(s) => s.hide = hide,
+            _ => {}
+        }
+    }
+
+    /// The timelines that place the light. Moving a spot light moves
+    /// its target along with it.
+    pub(crate) fn timelines_mut(&mut self) -> Vec<&mut TransformTimeline> {
+        match self {
+            Lights::Point(p) => vec![&mut p.timeline],
+            Lights::Spot(s) => vec![&mut s.timeline, &mut s.target_timeline],
+            Lights::Sun(s) => vec![&mut s.timeline],
+            _ => vec![],
+        }
+    }
+}
+
+/// A light must implement this to be sampled directly. Sample picks
+/// a direction from origin towards the light with a 2D number, it
+/// returns None if the light cannot be seen from origin. Pdf gives
+/// the density sample picks the unit length direction with along
+/// with how far away the light is that way, or None if the light is
+/// not in that direction.
+pub trait Light {
+    fn sample(&self, origin: &Point3, time: f64, u: (f64, f64)) -> Option<LightSample>;
+    fn pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> Option<(f64, f64)>;
+}
+
+/// Every light in a scene that is sampled directly. One is picked
+/// evenly for each sample.
+#[derive(Debug, Clone, Default)]
+pub struct LightList {
+    lights: Vec<Lights>,
+}
+
+impl LightList {
+    /// Finds the lights among the objects of a scene, every visible
+    /// shape with an emissive material along with the sky if it is
+    /// not black. Emitters inside of lists and BVHs are found too.
+    /// The visible lights in added are sampled along with them.
+    pub fn new(objects: &[Hittables], added: &[Lights], sky: &Skybox) -> LightList {
+        let mut lights = vec![];
+        find_emitters(objects, &mut lights);
+        lights.extend(added.iter().filter(|l| !l.hidden()).cloned());
+        lights.extend(EnvironmentLight::new(sky).map(Lights::Environment));
+
+        LightList { lights }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Picks a light with u_light and a direction towards it with u.
+    /// The pdf of the sample includes the chance of picking the light.
+    pub fn sample(
+        &self,
+        origin: &Point3,
+        time: f64,
+        u_light: f64,
+        u: (f64, f64),
+    ) -> Option<LightSample> {
+        let count = self.lights.len();
+        if count == 0 {
+            return None;
+        }
+
+        let index = ((u_light * count as f64) as usize).min(count - 1);
+        let mut sample = self.lights[index].sample(origin, time, u)?;
+        sample.pdf /= count as f64;
+
+        Some(sample)
+    }
+
+    /// The density sample would have picked the direction of r with.
+    /// r first hits something at t, infinity if it escapes to the sky.
+    /// Only the lights it hits there count, the ones behind are hidden.
+    pub fn pdf(&self, r: &Ray, t: f64) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+
+        let length = r.direction().length();
+        let (direction, distance) = (r.direction().clone() / length, t * length);
+
+        let total: f64 = self
+            .lights
+            .iter()
+            .filter_map(|light| light.pdf(r.origin(), &direction, r.time()))
+            .filter(|&(_, d)| d == distance || (d - distance).abs() <= 1e-6 * distance.max(1.0))
+            .map(|(pdf, _)| pdf)
+            .sum();
+
+        total / self.lights.len() as f64
+    }
+}
+
+/// Two unit vectors at right angles to w and each other
+fn perpendicular(w: &Vec3) -> (Vec3, Vec3) {
+    let helper = if w.x().abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let a = w.cross(&helper).unit_vector();
+    let b = w.cross(&a);
+
+    (a, b)
+}
+
+/// Every visible emissive surface, looking inside of lists and BVHs
+fn find_emitters<'a>(objects: impl IntoIterator<Item = &'a Hittables>, lights: &mut Vec<Lights>) {
+    for obj in objects {
+        match obj {
+            Hittables::Sphere(s) if !s.hide && s.material().is_emissive() => {
+                lights.push(Lights::Area(AreaLight::Sphere(s.clone())));
+            }
+            Hittables::Triangle(t) if !t.hide && t.material().is_emissive() => {
+                lights.push(Lights::Area(AreaLight::Triangle(t.clone())));
+            }
+            Hittables::HitList(l) => find_emitters(l.get_objs(), lights),
+            Hittables::BVHWrapper(b) => find_emitters(b.children(), lights),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        materials::{Materials, diffuse_light::DiffuseLight},
+        objects::{bvhwrapper::BVHWrapper, hitlist::HitList, sphere::Sphere, triangle::Triangle},
+        utils::Color,
+    };
+
+    #[test]
+    fn emitters_inside_a_bvh_are_found() {
+        let light = Materials::DiffuseLight(DiffuseLight::new_from_color(Color::white(), 2.0));
+        let spheres = (0..3)
+            .map(|k| {
+                let center = Point3::new(3.0 * k as f64, 0.0, 0.0);
+                Hittables::Sphere(Sphere::new(center, 1.0, light.clone()))
+            })
+            .collect();
+        let bvh = BVHWrapper::new_wrapper(HitList::new(spheres));
+
+        let lights = LightList::new(&[bvh], &[], &Skybox::Solid(Radiance::black()));
+        assert_eq!(lights.lights.len(), 3);
+    }
+
+    #[test]
+    fn sample_and_pdf_agree() {
+        let light = Materials::DiffuseLight(DiffuseLight::new_from_color(Color::white(), 2.0));
+        let objects = vec![
+            Hittables::Sphere(Sphere::new(Point3::new(0.0, 4.0, 0.0), 1.0, light.clone())),
+            Hittables::Triangle(Triangle::new(
+                Point3::new(-1.0, 0.0, -3.0),
+                Point3::new(1.0, 0.0, -3.0),
+                Point3::new(0.0, 2.0, -3.0),
+                light,
+            )),
+        ];
+        let lights = LightList::new(&objects, &[], &Skybox::Default);
+        let origin = Point3::origin();
+
+        for k in 0..48 {
+            let u = ((k % 7) as f64 / 7.0 + 0.05, (k % 5) as f64 / 5.0 + 0.1);
+            let Some(sample) = lights.sample(&origin, 0.0, k as f64 / 48.0, u) else {
+                continue;
+            };
+            let r = Ray::new(origin.clone(), 2.0 * sample.direction.clone());
+
+            // The pdf of the hit includes every light at that spot
+            let pdf = lights.pdf(&r, sample.distance / 2.0);
+            assert!((pdf - sample.pdf).abs() <= 1e-9 * pdf);
+            if sample.distance.is_finite() {
+                assert_eq!(sample.radiance, Radiance::new(2.0, 2.0, 2.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::f64::consts::PI;
+
+use crate::{
+    lights::{Light, LightSample, perpendicular},
+    timeline::TransformTimeline,
+    utils::{Point3, Radiance, Vec3},
+};
+
+/// The position a timeline holds at time t
+fn position(timeline: &TransformTimeline, t: f64) -> Point3 {
+    let p = timeline.combine_and_compute(t);
+    Point3::new(p[0], p[1], p[2])
+}
+
+fn assert_not_negative(intensity: &Radiance) {
+    assert!(
+        intensity.r() >= 0.0 && intensity.g() >= 0.0 && intensity.b() >= 0.0,
+        "A light cannot have a negative intensity"
+    );
+}
+
+/// A light from a single point that shines evenly in every direction.
+/// Intensity is the light arriving one unit away, it falls off with
+/// the square of the distance.
+///
+/// WARNING: Do not mess with the id field if this is in a scene.
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    pub id: usize,
+    pub hide: bool,
+    pub timeline: TransformTimeline,
+    intensity: Radiance,
+}
+
+impl PointLight {
+    /// # Panics
+    /// Panics if the intensity is negative.
+    pub fn new(position: Point3, intensity: Radiance) -> PointLight {
+        assert_not_negative(&intensity);
+
+        PointLight {
+            id: 0,
+            hide: false,
+            timeline: TransformTimeline::new(position, Point3::origin(), 1.0),
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, origin: &Point3, time: f64, _u: (f64, f64)) -> Option<LightSample> {
+        let to_light = position(&self.timeline, time) - origin.clone();
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            radiance: self.intensity.clone() / (distance * distance),
+            direction: to_light / distance,
+            distance,
+            pdf: 1.0,
+            delta: true,
+        })
+    }
+
+    fn pdf(&self, _origin: &Point3, _direction: &Vec3, _time: f64) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+/// A point light that only shines inside of a cone around the
+/// direction towards target. The cone angles are the full width in
+/// degrees, inside the inner cone the light is at full intensity and
+/// it fades out until the outer cone. Falloff shapes the fade, 1
+/// fades evenly with the cosine and larger values fade faster.
+///
+/// WARNING: Do not mess with the id field if this is in a scene.
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+    pub id: usize,
+    pub hide: bool,
+    pub timeline: TransformTimeline,
+    pub target_timeline: TransformTimeline,
+    intensity: Radiance,
+    cos_inner: f64,
+    cos_outer: f64,
+    falloff: f64,
+}
+
+impl SpotLight {
+    /// # Panics
+    /// Panics if the intensity is negative, the cone angles are not
+    /// 0 <= inner <= outer <= 360 or the falloff is not positive.
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        intensity: Radiance,
+        inner_angle: f64,
+        outer_angle: f64,
+        falloff: f64,
+    ) -> SpotLight {
+        assert_not_negative(&intensity);
+        assert!(
+            0.0 <= inner_angle && inner_angle <= outer_angle && outer_angle <= 360.0,
+            "The spot light's cone angles must be 0 <= inner <= outer <= 360. You passed {inner_angle} and {outer_angle}"
+        );
+        assert!(falloff > 0.0, "The spot light's falloff must be positive");
+
+        SpotLight {
+            id: 0,
+            hide: false,
+            timeline: TransformTimeline::new(position, Point3::origin(), 1.0),
+            target_timeline: TransformTimeline::new(target, Point3::origin(), 1.0),
+            intensity,
+            cos_inner: (inner_angle.to_radians() / 2.0).cos(),
+            cos_outer: (outer_angle.to_radians() / 2.0).cos(),
+            falloff,
+        }
+    }
+
+    /// How much of the intensity leaves in a direction with this cosine
+    /// to the cone's axis
+    fn cone_falloff(&self, cos: f64) -> f64 {
+        if cos >= self.cos_inner {
+            return 1.0;
+        }
+        if cos <= self.cos_outer {
+            return 0.0;
+        }
+
+        ((cos - self.cos_outer) / (self.cos_inner - self.cos_outer)).powf(self.falloff)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, origin: &Point3, time: f64, _u: (f64, f64)) -> Option<LightSample> {
+        let from = position(&self.timeline, time);
+        let axis = (position(&self.target_timeline, time) - from.clone()).unit_vector();
+
+        let to_light = from - origin.clone();
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = to_light / distance;
+
+        let falloff = self.cone_falloff(-direction.dot(&axis));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            radiance: self.intensity.clone() * (falloff / (distance * distance)),
+            direction,
+            distance,
+            pdf: 1.0,
+            delta: true,
+        })
+    }
+
+    fn pdf(&self, _origin: &Point3, _direction: &Vec3, _time: f64) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+/// Light from very far away that arrives from the same direction
+/// everywhere. The timeline holds a point in the direction of the
+/// sun as seen from the origin, moving it moves the sun across the
+/// sky. Irradiance is the light falling on a surface facing the sun.
+/// A sun with an angular diameter in degrees casts soft shadows.
+///
+/// WARNING: Do not mess with the id field if this is in a scene.
+#[derive(Debug, Clone)]
+pub struct SunLight {
+    pub id: usize,
+    pub hide: bool,
+    pub timeline: TransformTimeline,
+    irradiance: Radiance,
+    cos_radius: f64,
+}
+
+impl SunLight {
+    /// # Panics
+    /// Panics if the irradiance is negative or the angular diameter is
+    /// not in [0, 180).
+    pub fn new(direction: Vec3, irradiance: Radiance, angular_diameter: f64) -> SunLight {
+        assert_not_negative(&irradiance);
+        assert!(
+            (0.0..180.0).contains(&angular_diameter),
+            "The sun's angular diameter must be in [0, 180). You passed {angular_diameter}"
+        );
+
+        SunLight {
+            id: 0,
+            hide: false,
+            timeline: TransformTimeline::new(direction, Point3::origin(), 1.0),
+            irradiance,
+            cos_radius: (angular_diameter.to_radians() / 2.0).cos(),
+        }
+    }
+}
+
+impl Light for SunLight {
+    fn sample(&self, _origin: &Point3, time: f64, u: (f64, f64)) -> Option<LightSample> {
+        let towards = position(&self.timeline, time);
+        if towards.near_zero() {
+            return None;
+        }
+        let w = towards.unit_vector();
+
+        // Every point of the disk is as bright, together they give
+        // the irradiance
+        let direction = if self.cos_radius < 1.0 {
+            let cos_theta = 1.0 - u.0 * (1.0 - self.cos_radius);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * u.1;
+
+            let (a, b) = perpendicular(&w);
+            (sin_theta * phi.cos()) * a + (sin_theta * phi.sin()) * b + cos_theta * w
+        } else {
+            w
+        };
+
+        Some(LightSample {
+            radiance: self.irradiance.clone(),
+            direction,
+            distance: f64::INFINITY,
+            pdf: 1.0,
+            delta: true,
+        })
+    }
+
+    fn pdf(&self, _origin: &Point3, _direction: &Vec3, _time: f64) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        camera::{Integrators, PathIntegrator, Ray, RenderWorld, Sampler, SamplingMethod},
+        lights::{LightList, Lights},
+        materials::{Materials, lambertian::Lambertian},
+        objects::{Hittables, hitlist::HitList, sphere::Sphere},
+        scene::Skybox,
+        utils::Color,
+    };
+
+    #[test]
+    fn punctual_lights_cast_shadows() {
+        let gray =
+            Materials::Lambertian(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5), 1.0));
+        let floor = Hittables::Sphere(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, gray));
+        let bulb = Lights::Point(PointLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Radiance::new(4.0, 4.0, 4.0),
+        ));
+        let spot = Lights::Spot(SpotLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::new(5.0, 0.0, 0.0),
+            Radiance::new(4.0, 4.0, 4.0),
+            10.0,
+            20.0,
+            1.0,
+        ));
+        let sky = Skybox::Solid(Radiance::black());
+        let mut sampler = Sampler::new(SamplingMethod::Square, 1, 0);
+        let ray = Ray::new(Point3::new(0.0, 1.0, 3.0), Point3::new(0.0, -1.0, -3.0));
+
+        let mut seen = |objects: Vec<Hittables>, light: &Lights| {
+            let lights = LightList::new(&objects, std::slice::from_ref(light), &sky);
+            let objects = Arc::new(Hittables::HitList(HitList::new(objects)));
+            let world = RenderWorld::new(objects, lights, sky.clone());
+            sampler.start_pixel_sample(0, 0, 0);
+
+            Integrators::Path(PathIntegrator::new(2, None))
+                .radiance(ray.clone(), &world, &mut sampler)
+                .r()
+        };
+
+        // Lit from straight above by the bulb, one unit of light arrives
+        let lit = seen(vec![floor.clone()], &bulb);
+        assert!((lit - 0.5 / std::f64::consts::PI).abs() < 1e-6);
+
+        // The spot points away and a ball blocks the bulb
+        assert_eq!(seen(vec![floor.clone()], &spot), 0.0);
+        let ball = Hittables::Sphere(Sphere::new(
+            Point3::new(0.0, 1.0, 0.0),
+            0.2,
+            Materials::Lambertian(Lambertian::new_from_color(Color::black(), 1.0)),
+        ));
+        assert_eq!(seen(vec![floor, ball], &bulb), 0.0);
+    }
+}
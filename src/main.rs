@@ -1,14 +1,95 @@
+use std::time::Duration;
+
 use clap::Parser;
-use crucible::demo_builder::{demo_images, demo_movies};
+use crucible::{
+    camera::{
+        CropOutput, Integrators, OutputFormat, PixelFilter, SamplingMethod, SensorSize,
+        StereoLayout, ToneMapper,
+    },
+    demo_builder::{demo_images, demo_movies},
+};
 
 /// A ray-tracing renderer
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     /// File to render to. Note: you do not need to put the file extension. This
-    /// will be added for you
+    /// will be added for you. If you do add one (.ppm, .png, .exr or .hdr) the
+    /// image is saved in that format
     #[arg(short, long)]
     file: String,
+    /// The image format to save as when the file has no extension. Defaults
+    /// to ppm
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+    /// How bright light is compressed for png and ppm images. Defaults to
+    /// clamp
+    #[arg(long, value_enum)]
+    tone_map: Option<ToneMapper>,
+    /// The brightness that extended-reinhard maps to pure white. Defaults to
+    /// 4.0
+    #[arg(long)]
+    white_point: Option<f64>,
+    /// Brightens or darkens the image, measured in stops
+    #[arg(long)]
+    exposure: Option<f64>,
+    /// How sample positions are picked. Defaults to square
+    #[arg(long, value_enum)]
+    sampler: Option<SamplingMethod>,
+    /// How samples are spread over nearby pixels. Defaults to box
+    #[arg(long, value_enum)]
+    filter: Option<PixelFilter>,
+    /// How far the filter reaches in pixels. Defaults to 0.5 for box and 1.5
+    /// for the other filters
+    #[arg(long)]
+    filter_radius: Option<f64>,
+    /// The seed for the render's random numbers. The same seed always gives
+    /// the same image. Defaults to 0
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Saves a checkpoint of the render every this many seconds, so it can be
+    /// continued with --resume if it is stopped
+    #[arg(long)]
+    checkpoint_every: Option<f64>,
+    /// Continues a stopped render from its checkpoint. Movies also skip the
+    /// frames that were already rendered
+    #[arg(long)]
+    resume: bool,
+    /// Replaces the field of view with a lens of this focal length in mm
+    #[arg(long)]
+    focal_length: Option<f64>,
+    /// The sensor behind --focal-length. Defaults to full-frame
+    #[arg(long, value_enum)]
+    sensor: Option<SensorSize>,
+    /// The aperture of the lens as an f-number, needs --focal-length
+    #[arg(long)]
+    f_stop: Option<f64>,
+    /// Sets the brightness like a camera's ISO would, at f/16 unless --f-stop
+    /// is given
+    #[arg(long)]
+    iso: Option<f64>,
+    /// Only renders a window of the image, given as x0,y0,x1,y1 in pixels. The
+    /// end is exclusive
+    #[arg(long, value_delimiter = ',')]
+    crop: Option<Vec<u32>>,
+    /// Saves the full frame with black around the crop window instead of just
+    /// the window
+    #[arg(long)]
+    crop_full_frame: bool,
+    /// Renders a left and a right eye this far apart, in world units
+    #[arg(long)]
+    stereo: Option<f64>,
+    /// The distance both eyes of --stereo frame the same window at. Defaults
+    /// to parallel eyes
+    #[arg(long)]
+    convergence: Option<f64>,
+    /// How the eyes of --stereo are saved. Defaults to side-by-side
+    #[arg(long, value_enum)]
+    stereo_layout: Option<StereoLayout>,
+    /// Renders a view of the scene for lookdev and debugging instead of the
+    /// full path tracer
+    #[arg(long, value_enum)]
+    view: Option<Integrators>,
     /// How many threads to use
     #[arg(short, long)]
     threads: Option<usize>,
@@ -53,6 +134,8 @@ fn main() {
         scene = match args.world {
             1 => demo_movies::first_movie(threads, frame_rate, duration),
             2 => demo_movies::moving_teapot(threads, frame_rate, duration),
+            3 => demo_movies::sunset(threads, frame_rate, duration),
+            4 => demo_movies::fireball(threads, frame_rate, duration),
             _ => {
                 eprintln!("Invalid world number. Selecting default scene");
                 demo_movies::first_movie(threads, frame_rate, duration)
@@ -68,6 +151,8 @@ fn main() {
             3 => demo_images::load_teapot(threads),
             4 => demo_images::earth(threads),
             5 => demo_images::garden_skybox(threads),
+            6 => demo_images::cornell_box(threads),
+            7 => demo_images::cornell_smoke(threads),
             _ => {
                 eprintln!("Invalid world number. Selecting default scene");
                 demo_images::book1_end_scene(threads)
@@ -75,5 +160,78 @@ fn main() {
         };
     }
 
+    if let Some(format) = args.format {
+        scene.set_output_format(format);
+    }
+
+    if let Some(mut mapper) = args.tone_map {
+        if let (ToneMapper::ExtendedReinhard { white_point }, Some(w)) =
+            (&mut mapper, args.white_point)
+        {
+            *white_point = w;
+        }
+        scene.scene_cam.set_tone_mapper(mapper);
+    }
+
+    if let Some(stops) = args.exposure {
+        scene.scene_cam.set_exposure(stops);
+    }
+
+    if let Some(method) = args.sampler {
+        scene.scene_cam.set_sampling_method(method);
+    }
+
+    if let Some(filter) = args.filter {
+        let default_radius = if filter == PixelFilter::Box { 0.5 } else { 1.5 };
+        let radius = args.filter_radius.unwrap_or(default_radius);
+        scene.scene_cam.set_pixel_filter(filter, radius);
+    }
+
+    if let Some(seed) = args.seed {
+        scene.set_seed(seed);
+    }
+
+    if let Some(focal_length) = args.focal_length {
+        let sensor = args.sensor.unwrap_or(SensorSize::FullFrame);
+        scene.scene_cam.set_focal_length(focal_length, sensor);
+    }
+
+    if let Some(f_stop) = args.f_stop {
+        scene.scene_cam.set_f_number(f_stop);
+    }
+
+    if let Some(iso) = args.iso {
+        scene.scene_cam.set_iso(iso);
+    }
+
+    if let Some(crop) = args.crop {
+        assert!(
+            crop.len() == 4,
+            "The crop window needs 4 numbers, x0,y0,x1,y1. Got {crop:?}"
+        );
+        let output = if args.crop_full_frame {
+            CropOutput::FullFrame
+        } else {
+            CropOutput::Cropped
+        };
+        scene.set_crop_window(crop[0], crop[1], crop[2], crop[3], output);
+    }
+
+    if let Some(interocular) = args.stereo {
+        let layout = args.stereo_layout.unwrap_or(StereoLayout::SideBySide);
+        let convergence = args.convergence.unwrap_or(f64::INFINITY);
+        scene.set_stereo(interocular, convergence, layout);
+    }
+
+    if let Some(view) = args.view {
+        scene.set_integrator(view);
+    }
+
+    if let Some(seconds) = args.checkpoint_every {
+        scene.set_checkpoint_interval(Some(Duration::from_secs_f64(seconds)));
+    }
+
+    scene.set_resume(args.resume);
+
     scene.render_scene(args.file.as_str());
 }
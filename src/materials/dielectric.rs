@@ -1,20 +1,38 @@
-use rand::Rng;
+use crate::{
+    camera::{Ray, Sampler},
+    materials::Material,
+    objects::HitRecord,
+    utils::{Color, Vec3},
+};
 
-use crate::{camera::Ray, materials::Material, objects::HitRecord, utils::{Color, Vec3}};
-
-/// A material representing water, or glass
-/// 
-/// TODO: Beer's law and volumetric meshes
+/// A material representing water, or glass. Tinted glass absorbs
+/// light the further it travels inside following Beer's law, wrap the
+/// object in a ConstantMedium for glass that scatters light too.
 #[derive(Debug, Clone)]
 pub struct Dielectric {
     refraction_index: f64,
+    /// The share of the light left after one unit inside
+    tint: Color,
 }
 
 impl Dielectric {
     /// Creates a new dielectric with an index of
     /// refraction
     pub fn new(refraction_index: f64) -> Dielectric {
-        Dielectric { refraction_index }
+        Dielectric {
+            refraction_index,
+            tint: Color::white(),
+        }
+    }
+
+    /// Creates a dielectric that light takes the color tint in after
+    /// travelling one unit inside of it. Light that travels further
+    /// gets darker, twice as far is tint squared.
+    pub fn new_tinted(refraction_index: f64, tint: Color) -> Dielectric {
+        Dielectric {
+            refraction_index,
+            tint,
+        }
     }
 
     /// Schlick's Approximation for the Fresnel factor
@@ -27,8 +45,24 @@ impl Dielectric {
 
 impl Material for Dielectric {
     // figure out a way to get the refraction to realize what it is before it enters
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color) -> Option<Ray> {
-        *attenuation = Color::new(1.0, 1.0, 1.0);
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        sampler: &mut Sampler,
+    ) -> Option<Ray> {
+        // Leaving the object, the ray travelled from the last hit to here inside
+        *attenuation = if rec.front_face() {
+            Color::white()
+        } else {
+            let distance = rec.t() * r_in.direction().length();
+            Color::new(
+                self.tint.r().powf(distance),
+                self.tint.g().powf(distance),
+                self.tint.b().powf(distance),
+            )
+        };
 
         let ri = if rec.front_face() {
             1.0 / self.refraction_index
@@ -42,10 +76,8 @@ impl Material for Dielectric {
 
         let cannot_refract = ri * sin_theta > 1.0;
 
-        let mut rng = rand::rng();
-
         let direction =
-            if cannot_refract || Dielectric::reflectance(cos_theta, ri) > rng.random::<f64>() {
+            if cannot_refract || Dielectric::reflectance(cos_theta, ri) > sampler.get_1d() {
                 Vec3::reflect(&unit_direction, &rec.normal())
             } else {
                 Vec3::refract(&unit_direction, &rec.normal(), ri)
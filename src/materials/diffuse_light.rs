@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use crate::{
+    camera::{Ray, Sampler},
+    materials::Material,
+    objects::HitRecord,
+    textures::{Textures, solid_color::SolidColor},
+    utils::{Color, Radiance},
+};
+
+/// A material that gives off light evenly in every direction. It
+/// glows on both sides and does not reflect anything. Intensity
+/// scales the color, so an intensity above 1 is brighter than a
+/// white surface in the default sky.
+#[derive(Debug, Clone)]
+pub struct DiffuseLight {
+    tex: Arc<Textures>,
+    intensity: f64,
+}
+
+impl DiffuseLight {
+    /// Creates a light of a single color.
+    ///
+    /// # Panics
+    /// Panics if the intensity is negative.
+    pub fn new_from_color(c: Color, intensity: f64) -> DiffuseLight {
+        DiffuseLight::new_from_texture(
+            Arc::new(Textures::SolidColor(SolidColor::new_from_color(c))),
+            intensity,
+        )
+    }
+
+    /// Creates a light whose color comes from a texture.
+    ///
+    /// # Panics
+    /// Panics if the intensity is negative.
+    pub fn new_from_texture(tex: Arc<Textures>, intensity: f64) -> DiffuseLight {
+        assert!(intensity >= 0.0, "A light cannot have a negative intensity");
+        DiffuseLight { tex, intensity }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _attenuation: &mut Color,
+        _sampler: &mut Sampler,
+    ) -> Option<Ray> {
+        None
+    }
+
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Radiance {
+        let color = self
+            .tex
+            .value(rec.u_texture, rec.v_texture, &rec.position());
+
+        Radiance::from(color) * self.intensity
+    }
+}
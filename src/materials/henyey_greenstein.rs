@@ -0,0 +1,109 @@
+use std::f64::consts::PI;
+
+use crate::{
+    camera::{Ray, Sampler},
+    materials::Material,
+    objects::HitRecord,
+    utils::{Color, Radiance, Vec3},
+};
+
+/// How light scatters inside of a participating medium such as fog
+/// or smoke. G picks the shape, 0 scatters evenly in every direction,
+/// towards 1 light keeps going mostly the way it was and towards -1
+/// it mostly turns back. Albedo is the share of the light that is
+/// scattered instead of absorbed. Hot media such as fire also give
+/// off light, see set_emission.
+#[derive(Debug, Clone)]
+pub struct HenyeyGreenstein {
+    albedo: Color,
+    g: f64,
+    emission: Radiance,
+}
+
+impl HenyeyGreenstein {
+    /// # Panics
+    /// Panics if g is not in (-1, 1).
+    pub fn new(albedo: Color, g: f64) -> HenyeyGreenstein {
+        assert!(
+            -1.0 < g && g < 1.0,
+            "The phase function's g must be in (-1, 1). You passed {g}"
+        );
+
+        HenyeyGreenstein {
+            albedo,
+            g,
+            emission: Radiance::black(),
+        }
+    }
+
+    pub(crate) fn albedo(&self) -> &Color {
+        &self.albedo
+    }
+
+    /// Sets the light given off where a ray stops in the medium, this
+    /// is black unless set
+    pub fn set_emission(&mut self, emission: Radiance) {
+        self.emission = emission;
+    }
+
+    /// The density of scattering by an angle with this cosine, per
+    /// unit solid angle
+    fn phase(&self, cos: f64) -> f64 {
+        let denom = 1.0 + self.g * self.g - 2.0 * self.g * cos;
+
+        (1.0 - self.g * self.g) / (4.0 * PI * denom * denom.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        sampler: &mut Sampler,
+    ) -> Option<Ray> {
+        let (u, v) = sampler.get_2d();
+        let g = self.g;
+
+        // Invert the phase function's distribution of cosines
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+
+        let w = r_in.direction().clone().unit_vector();
+        let helper = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let a = w.cross(&helper).unit_vector();
+        let b = w.cross(&a);
+        let direction = (sin_theta * phi.cos()) * a + (sin_theta * phi.sin()) * b + cos_theta * w;
+
+        *attenuation = self.albedo.clone();
+        Some(Ray::new_at_time(rec.position(), direction, r_in.time()))
+    }
+
+    /// Scatter picks directions by the phase function, so the albedo
+    /// is all that is left
+    fn eval(&self, r_in: &Ray, _rec: &HitRecord, direction: &Vec3) -> Option<(Radiance, f64)> {
+        let cos = r_in
+            .direction()
+            .clone()
+            .unit_vector()
+            .dot(&direction.clone().unit_vector());
+        let phase = self.phase(cos);
+
+        Some((Radiance::from(self.albedo.clone()) * phase, phase))
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Radiance {
+        self.emission.clone()
+    }
+}
@@ -1,13 +1,11 @@
-use std::sync::Arc;
-
-use rand::Rng;
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::{
-    camera::Ray,
+    camera::{Ray, Sampler},
     materials::Material,
     objects::HitRecord,
-    textures::{solid_color::SolidColor, Textures},
-    utils::{Color, Vec3},
+    textures::{Textures, solid_color::SolidColor},
+    utils::{Color, Radiance, Vec3},
 };
 
 /// Lambertian is a material that allows solid color
@@ -37,8 +35,14 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color) -> Option<Ray> {
-        let mut scatter_dir = rec.normal() + Vec3::random_unit_vector();
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        sampler: &mut Sampler,
+    ) -> Option<Ray> {
+        let mut scatter_dir = rec.normal() + Vec3::unit_vector_from_sample(sampler.get_2d());
 
         if scatter_dir.near_zero() {
             scatter_dir = rec.normal().clone();
@@ -51,12 +55,25 @@ impl Material for Lambertian {
             .value(rec.u_texture, rec.v_texture, &rec.position())
             / self.scatter_prob;
 
-        let mut rng = rand::rng();
-
-        if rng.random::<f64>() <= self.scatter_prob {
+        if sampler.get_1d() <= self.scatter_prob {
             Some(scattered)
         } else {
             None
         }
     }
+
+    /// Scatter picks directions by the cosine to the normal, the
+    /// chance to scatter only decides if the ray keeps going
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Option<(Radiance, f64)> {
+        let cos = rec.normal().dot(&direction.clone().unit_vector());
+        if cos <= 0.0 {
+            return Some((Radiance::black(), 0.0));
+        }
+
+        let albedo = self
+            .tex
+            .value(rec.u_texture, rec.v_texture, &rec.position());
+
+        Some((Radiance::from(albedo) * (cos / PI), cos / PI))
+    }
 }
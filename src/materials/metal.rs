@@ -1,5 +1,5 @@
 use crate::{
-    camera::Ray,
+    camera::{Ray, Sampler},
     materials::Material,
     objects::HitRecord,
     utils::{Color, Vec3},
@@ -26,9 +26,16 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color) -> Option<Ray> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        sampler: &mut Sampler,
+    ) -> Option<Ray> {
         let reflected = Vec3::reflect(r_in.direction(), &rec.normal());
-        let reflected = reflected.unit_vector() + (self.fuzz * Vec3::random_unit_vector());
+        let fuzz = Vec3::unit_vector_from_sample(sampler.get_2d());
+        let reflected = reflected.unit_vector() + (self.fuzz * fuzz);
 
         let scattered = Ray::new_at_time(rec.position(), reflected, r_in.time());
         *attenuation = self.albedo.clone();
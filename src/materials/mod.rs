@@ -1,11 +1,16 @@
 use crate::{
-    camera::Ray,
-    materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal},
+    camera::{Ray, Sampler},
+    materials::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, henyey_greenstein::HenyeyGreenstein,
+        lambertian::Lambertian, metal::Metal,
+    },
     objects::HitRecord,
-    utils::Color,
+    utils::{Color, Radiance, Vec3},
 };
 
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod henyey_greenstein;
 pub mod lambertian;
 pub mod metal;
 
@@ -17,14 +22,50 @@ pub enum Materials {
     Lambertian(Lambertian),
     Metal(Metal),
     Dielectric(Dielectric),
+    DiffuseLight(DiffuseLight),
+    HenyeyGreenstein(HenyeyGreenstein),
 }
 
 impl Materials {
-    pub fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color) -> Option<Ray> {
+    pub fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        sampler: &mut Sampler,
+    ) -> Option<Ray> {
         match self {
-            Materials::Lambertian(l) => l.scatter(r_in, rec, attenuation),
-            Materials::Metal(m) => m.scatter(r_in, rec, attenuation),
-            Materials::Dielectric(d) => d.scatter(r_in, rec, attenuation),
+            Materials::Lambertian(l) => l.scatter(r_in, rec, attenuation, sampler),
+            Materials::Metal(m) => m.scatter(r_in, rec, attenuation, sampler),
+            Materials::Dielectric(d) => d.scatter(r_in, rec, attenuation, sampler),
+            Materials::DiffuseLight(l) => l.scatter(r_in, rec, attenuation, sampler),
+            Materials::HenyeyGreenstein(h) => h.scatter(r_in, rec, attenuation, sampler),
+        }
+    }
+
+    pub fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: &Vec3) -> Option<(Radiance, f64)> {
+        match self {
+            Materials::Lambertian(l) => l.eval(r_in, rec, direction),
+            Materials::Metal(m) => m.eval(r_in, rec, direction),
+            Materials::Dielectric(d) => d.eval(r_in, rec, direction),
+            Materials::DiffuseLight(l) => l.eval(r_in, rec, direction),
+            Materials::HenyeyGreenstein(h) => h.eval(r_in, rec, direction),
+        }
+    }
+
+    /// Whether the material gives off light, these are sampled
+    /// directly as lights
+    pub fn is_emissive(&self) -> bool {
+        matches!(self, Materials::DiffuseLight(_))
+    }
+
+    pub fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Radiance {
+        match self {
+            Materials::Lambertian(l) => l.emitted(r_in, rec),
+            Materials::Metal(m) => m.emitted(r_in, rec),
+            Materials::Dielectric(d) => d.emitted(r_in, rec),
+            Materials::DiffuseLight(l) => l.emitted(r_in, rec),
+            Materials::HenyeyGreenstein(h) => h.emitted(r_in, rec),
         }
     }
 }
@@ -32,7 +73,31 @@ impl Materials {
 /// This trait defines the ray scattering
 /// behavior of a material. Scatter returns an option
 /// representing if the ray scattered or was absorbed (None)
-/// and updates a HitRecord describing the hit
+/// and updates a HitRecord describing the hit. Random choices
+/// should come from the sampler, which gives every bounce a 2D
+/// and a 1D number. Emitted is the light the material gives off
+/// towards r_in, most materials give off none.
+///
+/// Eval gives the BSDF times the cosine to the normal, or the phase
+/// function in a medium, for light arriving from direction and
+/// leaving along r_in, along with the
+/// density scatter picks direction with. Materials that only scatter
+/// in exact directions, like mirrors and glass, return None and
+/// lights are not sampled for them.
 pub trait Material {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color) -> Option<Ray>;
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        sampler: &mut Sampler,
+    ) -> Option<Ray>;
+
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Option<(Radiance, f64)> {
+        None
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Radiance {
+        Radiance::black()
+    }
 }
@@ -6,6 +6,9 @@ use crate::{
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+/// The thinnest a bounding box can be on any axis
+const MIN_THICKNESS: f64 = 0.0001;
+
 #[derive(EnumIter, Debug, Clone)]
 pub enum Axis {
     X,
@@ -33,9 +36,23 @@ impl Default for Aabb {
 }
 
 impl Aabb {
-    /// Takes 3 intervals to make an AABB
+    /// Takes 3 intervals to make an AABB. Sides thinner than
+    /// MIN_THICKNESS are padded since a flat box, like the box of
+    /// an axis aligned triangle, can never be hit.
     pub fn new_from_intervals(x: Interval, y: Interval, z: Interval) -> Aabb {
-        Aabb { x, y, z }
+        let pad = |i: Interval| {
+            if (0.0..MIN_THICKNESS).contains(&i.size()) {
+                i.pad(MIN_THICKNESS)
+            } else {
+                i
+            }
+        };
+
+        Aabb {
+            x: pad(x),
+            y: pad(y),
+            z: pad(z),
+        }
     }
 
     /// Makes the bounding box from two points representing
@@ -21,6 +21,8 @@ impl BVHWrapper {
                 Hittables::HitList(_) => true,
                 Hittables::Sphere(s) => !s.hide,
                 Hittables::Triangle(t) => !t.hide,
+                Hittables::ConstantMedium(m) => !m.hide,
+                Hittables::VoxelVolume(v) => !v.hide,
             })
             .cloned()
             .collect();
@@ -31,6 +33,11 @@ impl BVHWrapper {
         BVHWrapper::new_from_vec(visible_objects, 0, end)
     }
 
+    /// The two halves the tree splits into
+    pub(crate) fn children(&self) -> [&Hittables; 2] {
+        [&self.left, &self.right]
+    }
+
     pub fn new_from_vec(mut objects: Vec<Hittables>, start: usize, end: usize) -> Hittables {
         let bvh = BVHWrapper::help_generate(&mut objects, start, end);
 
@@ -53,13 +60,16 @@ impl BVHWrapper {
 
         let object_span = end - start;
 
+        // A single object is its own leaf, wrapping it would put it on
+        // both sides and media would be passed through twice
+        if object_span == 1 {
+            return objects[start].clone();
+        }
+
         let left;
         let right;
 
-        if object_span == 1 {
-            left = objects[start].clone();
-            right = objects[start].clone();
-        } else if object_span == 2 {
+        if object_span == 2 {
             left = objects[start].clone();
             right = objects[start + 1].clone();
         } else {
@@ -79,6 +89,42 @@ impl BVHWrapper {
         Hittables::BVHWrapper(BVHWrapper { left, right, bbox })
     }
 
+    /// The same as hit, boxes counts every box the ray is tested against
+    pub(crate) fn hit_counting(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        boxes: &mut u32,
+    ) -> Option<HitRecord> {
+        *boxes += 1;
+        if !self.bbox.hit(r, &mut ray_t.clone()) {
+            return None;
+        }
+
+        let hit_left = self.left.hit_counting(r, ray_t, boxes);
+        let closest = hit_left.as_ref().map_or(ray_t.max(), |item| item.t);
+        let hit_right = self
+            .right
+            .hit_counting(r, &Interval::new(ray_t.min(), closest), boxes);
+
+        hit_right.or(hit_left)
+    }
+
+    /// The light left after passing everything in the tree, see
+    /// Hittables::transmittance
+    pub fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        if !self.bbox.hit(r, &mut ray_t.clone()) {
+            return 1.0;
+        }
+
+        let left = self.left.transmittance(r, ray_t);
+        if left <= 0.0 {
+            return 0.0;
+        }
+
+        left * self.right.transmittance(r, ray_t)
+    }
+
     fn box_compare(a: &Hittables, b: &Hittables, axis_index: Axis) -> Ordering {
         let a_axis_interval = a.bounding_box().axis_interval(axis_index.clone());
         let b_axis_interval = b.bounding_box().axis_interval(axis_index.clone());
@@ -94,17 +140,14 @@ impl BVHWrapper {
 }
 
 impl Hittable for BVHWrapper {
-    fn hit(&mut self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        // The boxes already cover every position the objects take while
+        // the shutter is open, see Hittables::update_bb
+        // TODO: Add an AABB rotation method
         if !self.bbox.hit(r, &mut ray_t.clone()) {
             return None;
         }
 
-        // Update the AABBs based on the ray's time
-        // TODO: Add an AABB rotation method
-        let time = r.time();
-        self.left.update_bb(time);
-        self.right.update_bb(time);
-
         let hit_left = self.left.hit(r, ray_t);
         let hit_right = self.right.hit(
             r,
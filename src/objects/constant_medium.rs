@@ -0,0 +1,206 @@
+use crate::{
+    camera::Ray,
+    materials::{Materials, henyey_greenstein::HenyeyGreenstein},
+    objects::{HitRecord, Hittable, Hittables, RayRandom, bvh::Aabb},
+    timeline::TransformTimeline,
+    utils::{Color, Interval, Vec3},
+};
+
+/// A volume of smoke, mist or anything else that light scatters
+/// inside of. The medium fills a closed boundary, a sphere or a
+/// closed mesh with its normals facing out, and is equally dense
+/// everywhere inside. Rays scatter somewhere inside at random, denser
+/// media scatter them sooner. The boundary itself is never seen.
+///
+/// WARNING: Do not mess with the id field if this is in a
+/// scene.
+#[derive(Debug, Clone)]
+pub struct ConstantMedium {
+    pub id: usize,
+    pub hide: bool,
+    boundary: Box<Hittables>,
+    density: f64,
+    phase: Materials,
+}
+
+impl ConstantMedium {
+    /// Fills boundary with a medium. Density is how many times light
+    /// scatters per unit on average, albedo and g describe how it
+    /// scatters, see HenyeyGreenstein.
+    ///
+    /// # Panics
+    /// Panics if the density is negative or g is not in (-1, 1).
+    pub fn new(boundary: Hittables, density: f64, albedo: Color, g: f64) -> ConstantMedium {
+        assert!(
+            density >= 0.0,
+            "A medium cannot have a negative density. You passed {density}"
+        );
+
+        ConstantMedium {
+            id: 0,
+            hide: false,
+            boundary: Box::new(boundary),
+            density,
+            phase: Materials::HenyeyGreenstein(HenyeyGreenstein::new(albedo, g)),
+        }
+    }
+
+    pub fn update_bb(&mut self, window: &Interval) {
+        self.boundary.update_bb(window);
+    }
+
+    /// The timelines that place the boundary, moving them moves the
+    /// medium
+    pub(crate) fn timelines_mut(&mut self) -> Vec<&mut TransformTimeline> {
+        self.boundary.timelines_mut()
+    }
+
+    /// How much of the light along the ray gets through, the medium
+    /// dims it by Beer's law
+    pub(crate) fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        if self.hide || self.density <= 0.0 {
+            return 1.0;
+        }
+
+        let length = r.direction().length();
+        let inside: f64 = self
+            .inside_spans(r, ray_t, &mut 0)
+            .iter()
+            .map(|span| span.size() * length)
+            .sum();
+
+        (-self.density * inside).exp()
+    }
+
+    /// The parts of the interval that are inside of the boundary. The
+    /// boundary's crossings are walked in order, the ray is inside of
+    /// the medium whenever the next crossing leaves it. Boxes counts
+    /// the boxes of the boundary the ray is tested against.
+    fn inside_spans(&self, r: &Ray, ray_t: &Interval, boxes: &mut u32) -> Vec<Interval> {
+        let mut spans = Vec::new();
+
+        let mut t = ray_t.min();
+        loop {
+            let crossing = self
+                .boundary
+                .hit_counting(r, &Interval::new(t, f64::INFINITY), boxes);
+            let (end, inside) = match &crossing {
+                Some(c) => (c.t().min(ray_t.max()), !c.front_face()),
+                None => (ray_t.max(), false),
+            };
+
+            if inside {
+                spans.push(Interval::new(t, end));
+            }
+
+            match crossing {
+                Some(c) if c.t() < ray_t.max() => t = c.t() + 0.0001,
+                _ => return spans,
+            }
+        }
+    }
+
+    /// The same as hit, boxes counts every box of the boundary the
+    /// ray is tested against
+    pub(crate) fn hit_counting(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        boxes: &mut u32,
+    ) -> Option<HitRecord> {
+        if self.hide || self.density <= 0.0 {
+            return None;
+        }
+
+        let length = r.direction().length();
+        // How far the ray gets inside of the medium before it scatters
+        let seed = (self.id as u64) ^ self.density.to_bits();
+        let mut remaining = -(1.0 - RayRandom::new(r, seed).uniform()).ln() / self.density;
+
+        for span in self.inside_spans(r, ray_t, boxes) {
+            let distance = span.size() * length;
+            if remaining < distance {
+                let t_hit = span.min() + remaining / length;
+                let p = r.at(t_hit);
+
+                // Media have no surface so the normal is arbitrary
+                let normal = Vec3::new(1.0, 0.0, 0.0);
+                // Safety: the normal has unit length
+                let rec =
+                    unsafe { HitRecord::new(r, p, normal, t_hit, 0.0, 0.0, self.phase.clone()) };
+                return Some(rec);
+            }
+            remaining -= distance;
+        }
+
+        None
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        self.hit_counting(r, ray_t, &mut 0)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        materials::lambertian::Lambertian,
+        objects::{bvhwrapper::BVHWrapper, hitlist::HitList, sphere::Sphere},
+        utils::{Point3, Vec3},
+    };
+
+    #[test]
+    fn light_gets_through_by_beers_law() {
+        let glass = Materials::Lambertian(Lambertian::new_from_color(Color::black(), 1.0));
+        let boundary = Hittables::Sphere(Sphere::new(Point3::origin(), 1.0, glass));
+        let medium = ConstantMedium::new(boundary, 0.5, Color::white(), 0.0);
+
+        // Straight through the middle is two units of medium
+        let rays = 20_000;
+        let through = (0..rays)
+            .filter(|&k| {
+                let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0))
+                    .with_medium_sample((k as f64 + 0.5) / rays as f64);
+                medium
+                    .hit(&r, &Interval::new(0.001, f64::INFINITY))
+                    .is_none()
+            })
+            .count();
+        let expected = (-0.5f64 * 2.0).exp();
+        assert!((through as f64 / rays as f64 - expected).abs() < 0.02);
+
+        // Scattering happens inside, even for rays that start there
+        let inside = Ray::new(Point3::origin(), Vec3::new(0.0, 2.0, 0.0));
+        let rec = medium.hit(&inside, &Interval::new(0.001, f64::INFINITY));
+        if let Some(rec) = rec {
+            assert!(rec.position().length() < 1.0);
+        }
+        let past = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(
+            medium
+                .hit(&past, &Interval::new(0.001, f64::INFINITY))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn boxes_of_the_boundary_are_counted() {
+        let unseen = Materials::Lambertian(Lambertian::new_from_color(Color::black(), 1.0));
+        let spheres = [-2.0, 2.0]
+            .map(|x| Hittables::Sphere(Sphere::new(Point3::new(x, 0.0, 0.0), 1.0, unseen.clone())));
+        let boundary = BVHWrapper::new_wrapper(HitList::new(spheres.to_vec()));
+        let medium = ConstantMedium::new(boundary, 0.5, Color::white(), 0.0);
+
+        let r = Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut boxes = 0;
+        medium.hit_counting(&r, &Interval::new(0.001, f64::INFINITY), &mut boxes);
+        assert!(boxes > 0);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{camera::Ray, objects::{bvh::Aabb, HitRecord, Hittable, Hittables}, utils::Interval};
+use crate::{
+    camera::Ray,
+    objects::{HitRecord, Hittable, Hittables, bvh::Aabb},
+    timeline::TransformTimeline,
+    utils::Interval,
+};
 
 /// This is a general API to store world objects
 /// it also implements Hittable and handles hits for each
@@ -30,11 +35,53 @@ impl HitList {
         &self.objs
     }
 
-    pub fn update_bb(&mut self, time: f64) {
+    pub(crate) fn hit_counting(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        boxes: &mut u32,
+    ) -> Option<HitRecord> {
+        let mut rec: Option<HitRecord> = None;
+        let mut closest = ray_t.max();
+
+        for obj in self.objs.iter() {
+            let new_interval = Interval::new(ray_t.min(), closest);
+            if let Some(obj) = obj.hit_counting(r, &new_interval, boxes) {
+                closest = obj.t;
+                rec = Some(obj);
+            }
+        }
+
+        rec
+    }
+
+    /// The light left after passing every object in the list, see
+    /// Hittables::transmittance
+    pub fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        let mut transmittance = 1.0;
+
+        for obj in self.objs.iter() {
+            transmittance *= obj.transmittance(r, ray_t);
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+
+        transmittance
+    }
+
+    pub(crate) fn timelines_mut(&mut self) -> Vec<&mut TransformTimeline> {
+        self.objs
+            .iter_mut()
+            .flat_map(|obj| obj.timelines_mut())
+            .collect()
+    }
+
+    pub fn update_bb(&mut self, window: &Interval) {
         let mut bbox = Aabb::default();
 
         for obj in self.objs.iter_mut() {
-            obj.update_bb(time);
+            obj.update_bb(window);
             bbox = Aabb::new_from_boxes(&bbox, obj.bounding_box());
         }
 
@@ -49,11 +96,11 @@ impl Default for HitList {
 }
 
 impl Hittable for HitList {
-    fn hit(&mut self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
         let mut rec: Option<HitRecord> = None;
         let mut closest = ray_t.max();
 
-        for obj in self.objs.as_mut_slice() {
+        for obj in self.objs.iter() {
             let new_interval = Interval::new(ray_t.min(), closest);
             if let Some(obj) = obj.hit(r, &new_interval) {
                 closest = obj.t;
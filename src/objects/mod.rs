@@ -2,16 +2,20 @@ mod bvh;
 
 // Reexport the creatable objects
 pub mod bvhwrapper;
+pub mod constant_medium;
 pub mod hitlist;
 pub mod sphere;
 pub mod triangle;
+pub mod voxel_volume;
 
 use crate::{
     camera::Ray,
     materials::Materials,
     objects::{
-        bvh::Aabb, bvhwrapper::BVHWrapper, hitlist::HitList, sphere::Sphere, triangle::Triangle,
+        bvh::Aabb, bvhwrapper::BVHWrapper, constant_medium::ConstantMedium, hitlist::HitList,
+        sphere::Sphere, triangle::Triangle, voxel_volume::VoxelVolume,
     },
+    timeline::TransformTimeline,
     utils::{Interval, Point3, Vec3},
 };
 
@@ -98,6 +102,11 @@ impl HitRecord {
         self.front_face
     }
 
+    /// Where on the ray the hit is
+    pub fn t(&self) -> f64 {
+        self.t
+    }
+
     pub fn material(&self) -> Materials {
         self.mat.clone()
     }
@@ -112,15 +121,55 @@ pub enum Hittables {
     HitList(HitList),
     BVHWrapper(BVHWrapper),
     Triangle(Triangle),
+    ConstantMedium(ConstantMedium),
+    VoxelVolume(VoxelVolume),
 }
 
 impl Hittables {
-    pub fn hit(&mut self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    pub fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
         match self {
             Hittables::Sphere(s) => s.hit(r, ray_t),
             Hittables::HitList(l) => l.hit(r, ray_t),
             Hittables::BVHWrapper(b) => b.hit(r, ray_t),
             Hittables::Triangle(t) => t.hit(r, ray_t),
+            Hittables::ConstantMedium(m) => m.hit(r, ray_t),
+            Hittables::VoxelVolume(v) => v.hit(r, ray_t),
+        }
+    }
+
+    /// How much of the light along the ray makes it through the
+    /// interval. Surfaces in the way block all of it, media let some
+    /// of it through. Shadow rays use this instead of hit so media
+    /// dim the light smoothly instead of blocking it at random.
+    pub fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        match self {
+            Hittables::HitList(l) => l.transmittance(r, ray_t),
+            Hittables::BVHWrapper(b) => b.transmittance(r, ray_t),
+            Hittables::ConstantMedium(m) => m.transmittance(r, ray_t),
+            Hittables::VoxelVolume(v) => v.transmittance(r, ray_t),
+            _ => {
+                if self.hit(r, ray_t).is_some() {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+
+    /// Hits like hit but also counts the bounding boxes the ray is
+    /// tested against on the way, the BVH heatmap shows this.
+    pub(crate) fn hit_counting(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        boxes: &mut u32,
+    ) -> Option<HitRecord> {
+        match self {
+            Hittables::HitList(l) => l.hit_counting(r, ray_t, boxes),
+            Hittables::BVHWrapper(b) => b.hit_counting(r, ray_t, boxes),
+            Hittables::ConstantMedium(m) => m.hit_counting(r, ray_t, boxes),
+            _ => self.hit(r, ray_t),
         }
     }
 
@@ -130,24 +179,111 @@ impl Hittables {
             Hittables::HitList(l) => l.bounding_box(),
             Hittables::BVHWrapper(b) => b.bounding_box(),
             Hittables::Triangle(t) => t.bounding_box(),
+            Hittables::ConstantMedium(m) => m.bounding_box(),
+            Hittables::VoxelVolume(v) => v.bounding_box(),
         }
     }
 
-    /// Changes the AABB's position
-    pub fn update_bb(&mut self, time: f64) {
+    /// Grows the AABB to contain the object everywhere it moves
+    /// during the time window. A BVHWrapper keeps the boxes it was
+    /// built with so call this before wrapping.
+    pub fn update_bb(&mut self, window: &Interval) {
         match self {
-            Hittables::Sphere(s) => s.update_bb(time),
-            Hittables::HitList(l) => l.update_bb(time),
+            Hittables::Sphere(s) => s.update_bb(window),
+            Hittables::HitList(l) => l.update_bb(window),
             Hittables::BVHWrapper(_) => {}
-            Hittables::Triangle(t) => t.update_bb(time),
+            Hittables::Triangle(t) => t.update_bb(window),
+            Hittables::ConstantMedium(m) => m.update_bb(window),
+            Hittables::VoxelVolume(v) => v.update_bb(window),
+        }
+    }
+
+    /// Every timeline that places the object, a BVHWrapper has none
+    /// since it cannot move
+    pub(crate) fn timelines_mut(&mut self) -> Vec<&mut TransformTimeline> {
+        match self {
+            Hittables::Sphere(s) => vec![&mut s.timeline],
+            Hittables::HitList(l) => l.timelines_mut(),
+            Hittables::BVHWrapper(_) => vec![],
+            Hittables::Triangle(t) => {
+                vec![&mut t.a_timeline, &mut t.b_timeline, &mut t.c_timeline]
+            }
+            Hittables::ConstantMedium(m) => m.timelines_mut(),
+            Hittables::VoxelVolume(v) => {
+                vec![&mut v.min_timeline, &mut v.max_timeline]
+            }
+        }
+    }
+}
+
+/// Uniform numbers in [0, 1) for the media along a ray. Hits cannot
+/// take a sampler so the integrator hands the ray a number from it,
+/// see Ray::with_medium_sample. The first number is that one turned
+/// by the seed, so every medium sees its own number and the samples
+/// of a pixel stay spread out. The numbers after it and the numbers
+/// of rays without one come from hashing, the same ray always gets
+/// the same numbers whatever thread casts it.
+pub(crate) struct RayRandom {
+    state: u64,
+    first: Option<f64>,
+}
+
+impl RayRandom {
+    /// Hashes the ray into the seed, different seeds give different
+    /// numbers for the same ray
+    pub(crate) fn new(r: &Ray, seed: u64) -> RayRandom {
+        if let Some(u) = r.medium_sample() {
+            let turn = RayRandom {
+                state: seed,
+                first: None,
+            }
+            .uniform();
+
+            return RayRandom {
+                state: seed ^ u.to_bits(),
+                first: Some((u + turn).fract()),
+            };
+        }
+
+        let (o, d) = (r.origin(), r.direction());
+        let values = [o.x(), o.y(), o.z(), d.x(), d.y(), d.z(), r.time()];
+
+        let mut random = RayRandom {
+            state: seed,
+            first: None,
+        };
+        for v in values {
+            random.state ^= v.to_bits();
+            random.state = random.mix();
         }
+
+        random
+    }
+
+    pub(crate) fn uniform(&mut self) -> f64 {
+        if let Some(u) = self.first.take() {
+            return u;
+        }
+
+        (self.mix() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// One step of SplitMix64
+    fn mix(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        z ^ (z >> 31)
     }
 }
 
 /// An object must implement this to be rendered. This function
 /// captures the hit data in rec and returns an option with some hit
-/// or none.
+/// or none. Hitting never changes the object so one world can be
+/// shared by every render thread.
 pub trait Hittable {
-    fn hit(&mut self, r: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord>;
     fn bounding_box(&self) -> &Aabb;
 }
@@ -45,28 +45,47 @@ impl Sphere {
         (phi / (2.0 * PI), theta / PI)
     }
 
-    pub fn update_bb(&mut self, time: f64) {
+    /// Sets the bounding box to contain the sphere for the whole
+    /// window. Checking the ends of the window and every keyframe
+    /// inside it is enough since the motion in between is linear.
+    pub fn update_bb(&mut self, window: &Interval) {
+        let mut bbox = Aabb::default();
+
+        for time in self.timeline.keyframe_times(window) {
+            bbox = Aabb::new_from_boxes(&bbox, &self.bb_at_time(time));
+        }
+
+        self.bbox = bbox;
+    }
+
+    /// Where the sphere is and how big it is at time
+    pub(crate) fn center_and_radius(&self, time: f64) -> (Point3, f64) {
         let sphere = self.timeline.combine_and_compute(time);
-        let current_center = Point3::new(sphere[0], sphere[1], sphere[2]);
-        let radius = sphere[3];
+
+        (Point3::new(sphere[0], sphere[1], sphere[2]), sphere[3])
+    }
+
+    pub(crate) fn material(&self) -> &Materials {
+        &self.mat
+    }
+
+    fn bb_at_time(&self, time: f64) -> Aabb {
+        let (current_center, radius) = self.center_and_radius(time);
 
         let rvec = Vec3::new(radius, radius, radius);
 
-        self.bbox =
-            Aabb::new_from_points(current_center.clone() - rvec.clone(), current_center + rvec)
+        Aabb::new_from_points(current_center.clone() - rvec.clone(), current_center + rvec)
     }
 }
 
 impl Hittable for Sphere {
-    fn hit(&mut self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
         if self.hide {
             return None;
         }
 
         // Calculate the position of our sphere using our timeline
-        let sphere = self.timeline.combine_and_compute(r.time());
-        let current_center = Point3::new(sphere[0], sphere[1], sphere[2]);
-        let radius = sphere[3];
+        let (current_center, radius) = self.center_and_radius(r.time());
 
         let oc = current_center.clone() - r.origin().clone(); // (C - P) part of the circle eqn
 
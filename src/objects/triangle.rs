@@ -61,14 +61,36 @@ impl Triangle {
         (x, y, z)
     }
 
-    pub fn update_bb(&mut self, time: f64) {
-        let a = self.a_timeline.combine_and_compute(time);
-        let b = self.b_timeline.combine_and_compute(time);
-        let c = self.c_timeline.combine_and_compute(time);
+    /// Sets the bounding box to contain the triangle for the whole
+    /// window. The vertices move linearly between keyframes so the
+    /// window ends and the keyframes inside it are enough to check.
+    pub fn update_bb(&mut self, window: &Interval) {
+        let mut times = self.a_timeline.keyframe_times(window);
+        times.extend(self.b_timeline.keyframe_times(window));
+        times.extend(self.c_timeline.keyframe_times(window));
+
+        let mut bbox = Aabb::default();
+        for time in times {
+            bbox = Aabb::new_from_boxes(&bbox, &self.bb_at_time(time));
+        }
+
+        self.bbox = bbox;
+    }
+
+    /// The corners of the triangle at time
+    pub(crate) fn vertices(&self, time: f64) -> [Point3; 3] {
+        [&self.a_timeline, &self.b_timeline, &self.c_timeline].map(|timeline| {
+            let p = timeline.combine_and_compute(time);
+            Point3::new(p[0], p[1], p[2])
+        })
+    }
+
+    pub(crate) fn material(&self) -> &Materials {
+        &self.mat
+    }
 
-        let a = Point3::new(a[0], a[1], a[2]);
-        let b = Point3::new(b[0], b[1], b[2]);
-        let c = Point3::new(c[0], c[1], c[2]);
+    fn bb_at_time(&self, time: f64) -> Aabb {
+        let [a, b, c] = self.vertices(time);
 
         let max_points = Triangle::max_points(&a, &b, &c);
         let min_points = Triangle::min_points(&a, &b, &c);
@@ -77,24 +99,18 @@ impl Triangle {
         let y_int = Interval::new(min_points.1, max_points.1);
         let z_int = Interval::new(min_points.2, max_points.2);
 
-        self.bbox = Aabb::new_from_intervals(x_int, y_int, z_int);
+        Aabb::new_from_intervals(x_int, y_int, z_int)
     }
 }
 
 impl Hittable for Triangle {
     /// Based on the Moller-Trumbore algorithm
-    fn hit(&mut self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
         if self.hide {
             return None;
         }
 
-        let a = self.a_timeline.combine_and_compute(r.time());
-        let b = self.b_timeline.combine_and_compute(r.time());
-        let c = self.c_timeline.combine_and_compute(r.time());
-
-        let a = Point3::new(a[0], a[1], a[2]);
-        let b = Point3::new(b[0], b[1], b[2]);
-        let c = Point3::new(c[0], c[1], c[2]);
+        let [a, b, c] = self.vertices(r.time());
 
         let e1 = b.clone() - a.clone();
         let e2 = c.clone() - a.clone();
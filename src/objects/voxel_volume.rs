@@ -0,0 +1,506 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    camera::Ray,
+    materials::{Materials, henyey_greenstein::HenyeyGreenstein},
+    objects::{HitRecord, Hittable, RayRandom, bvh::Aabb},
+    timeline::TransformTimeline,
+    utils::{Color, Interval, Point3, Radiance, Vec3},
+};
+
+/// The values of one channel of a voxel grid. Voxels are numbered
+/// with x changing fastest, then y, then z.
+#[derive(Debug, Clone)]
+pub enum Voxels {
+    /// A value for every voxel
+    Dense(Vec<f32>),
+    /// Only the voxels that are not 0, by number
+    Sparse(HashMap<usize, f32>),
+}
+
+impl Voxels {
+    fn get(&self, index: usize) -> f64 {
+        match self {
+            Voxels::Dense(values) => values[index] as f64,
+            Voxels::Sparse(values) => values.get(&index).copied().unwrap_or(0.0) as f64,
+        }
+    }
+
+    /// True if every value that is kept passes check
+    pub(crate) fn all(&self, check: impl Fn(f32) -> bool) -> bool {
+        match self {
+            Voxels::Dense(values) => values.iter().all(|&v| check(v)),
+            Voxels::Sparse(values) => values.values().all(|&v| check(v)),
+        }
+    }
+
+    fn max(&self) -> f64 {
+        let max = match self {
+            Voxels::Dense(values) => values.iter().fold(0.0f32, |max, &v| max.max(v)),
+            Voxels::Sparse(values) => values.values().fold(0.0f32, |max, &v| max.max(v)),
+        };
+
+        max as f64
+    }
+}
+
+/// A box of voxels holding a density and optionally a temperature,
+/// usually loaded from disk with `asset_loader::vol_loader`. Density
+/// is how many times light scatters or is absorbed per unit on
+/// average, temperature is in Kelvin. Values between the voxel
+/// centers are blended trilinearly.
+#[derive(Debug, Clone)]
+pub struct VoxelGrid {
+    size: [usize; 3],
+    density: Voxels,
+    temperature: Option<Voxels>,
+    max_density: f64,
+}
+
+impl VoxelGrid {
+    /// # Panics
+    /// Panics if a size is 0, if a dense channel does not have a value
+    /// for every voxel, if a sparse channel has a voxel outside of the
+    /// grid, if a value is NaN or infinite or if a density is
+    /// negative.
+    pub fn new(size: [usize; 3], density: Voxels, temperature: Option<Voxels>) -> VoxelGrid {
+        assert!(
+            size.iter().all(|&n| n > 0),
+            "A voxel grid needs at least one voxel on each axis. You passed {size:?}"
+        );
+
+        let count = size[0] * size[1] * size[2];
+        for channel in std::iter::once(&density).chain(&temperature) {
+            match channel {
+                Voxels::Dense(values) => assert!(
+                    values.len() == count,
+                    "A {size:?} grid has {count} voxels but a channel has {} values",
+                    values.len()
+                ),
+                Voxels::Sparse(values) => assert!(
+                    values.keys().all(|&index| index < count),
+                    "A sparse channel has a voxel outside of the {size:?} grid"
+                ),
+            }
+            assert!(
+                channel.all(f32::is_finite),
+                "A voxel grid cannot hold NaN or infinite values"
+            );
+        }
+
+        assert!(
+            density.all(|v| v >= 0.0),
+            "A voxel grid cannot have a negative density"
+        );
+
+        let max_density = density.max();
+        VoxelGrid {
+            size,
+            density,
+            temperature,
+            max_density,
+        }
+    }
+
+    /// The density and temperature at local, where the grid spans
+    /// [0, 1] on every axis
+    pub(crate) fn lookup(&self, local: [f64; 3]) -> (f64, f64) {
+        // The two voxels on each axis to blend between and how far
+        // local is from the first one's center
+        let axes: [(usize, usize, f64); 3] = std::array::from_fn(|axis| {
+            let n = self.size[axis];
+            let x = (local[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (x.floor() as usize).min(n - 1);
+
+            (i, (i + 1).min(n - 1), x - i as f64)
+        });
+
+        let mut density = 0.0;
+        let mut temperature = 0.0;
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut voxel = [0; 3];
+            for (axis, &(i0, i1, f)) in axes.iter().enumerate() {
+                if corner & (1 << axis) == 0 {
+                    voxel[axis] = i0;
+                    weight *= 1.0 - f;
+                } else {
+                    voxel[axis] = i1;
+                    weight *= f;
+                }
+            }
+            if weight == 0.0 {
+                continue;
+            }
+
+            let index = voxel[0] + self.size[0] * (voxel[1] + self.size[1] * voxel[2]);
+            density += weight * self.density.get(index);
+            if let Some(t) = &self.temperature {
+                temperature += weight * t.get(index);
+            }
+        }
+
+        (density, temperature)
+    }
+}
+
+/// A cloud, a plume of smoke or a fireball whose density changes from
+/// place to place, read from a voxel grid. The grid is stretched
+/// between two corners that can be moved and scaled like the vertices
+/// of a triangle. Rays are tracked through the grid against its
+/// densest voxel, the steps that land on thinner voxels are skipped
+/// at random. Where a grid has a temperature the medium glows like a
+/// blackbody, see set_emission_scale.
+///
+/// WARNING: Do not mess with the id field if this is in a
+/// scene.
+#[derive(Debug, Clone)]
+pub struct VoxelVolume {
+    pub id: usize,
+    pub hide: bool,
+    pub min_timeline: TransformTimeline,
+    pub max_timeline: TransformTimeline,
+    grid: Arc<VoxelGrid>,
+    density_scale: f64,
+    emission_scale: f64,
+    phase: HenyeyGreenstein,
+    bbox: Aabb,
+}
+
+impl VoxelVolume {
+    /// Stretches the grid between the min and max corners, albedo and
+    /// g describe how it scatters, see HenyeyGreenstein.
+    ///
+    /// # Panics
+    /// Panics if g is not in (-1, 1).
+    pub fn new(
+        grid: Arc<VoxelGrid>,
+        min: Point3,
+        max: Point3,
+        albedo: Color,
+        g: f64,
+    ) -> VoxelVolume {
+        let min_timeline = TransformTimeline::new(min.clone(), Point3::origin(), 1.0);
+        let max_timeline = TransformTimeline::new(max.clone(), Point3::origin(), 1.0);
+
+        VoxelVolume {
+            id: 0,
+            hide: false,
+            min_timeline,
+            max_timeline,
+            grid,
+            density_scale: 1.0,
+            emission_scale: 1.0,
+            phase: HenyeyGreenstein::new(albedo, g),
+            bbox: Aabb::new_from_points(min, max),
+        }
+    }
+
+    /// Multiplies every density in the grid, this thins out or
+    /// thickens the volume without changing its shape
+    ///
+    /// # Panics
+    /// Panics if the scale is negative.
+    pub fn set_density_scale(&mut self, scale: f64) {
+        assert!(
+            scale >= 0.0,
+            "A volume's density scale cannot be negative. You passed {scale}"
+        );
+        self.density_scale = scale;
+    }
+
+    /// Multiplies the light given off by hot voxels. A voxel at 1000
+    /// Kelvin that absorbs all of its light glows with a brightest
+    /// channel of scale, the glow grows with the temperature to the
+    /// fourth power.
+    ///
+    /// # Panics
+    /// Panics if the scale is negative.
+    pub fn set_emission_scale(&mut self, scale: f64) {
+        assert!(
+            scale >= 0.0,
+            "A volume's emission scale cannot be negative. You passed {scale}"
+        );
+        self.emission_scale = scale;
+    }
+
+    /// Sets the bounding box to contain the volume for the whole
+    /// window. The corners move linearly between keyframes so the
+    /// window ends and the keyframes inside it are enough to check.
+    pub fn update_bb(&mut self, window: &Interval) {
+        let mut times = self.min_timeline.keyframe_times(window);
+        times.extend(self.max_timeline.keyframe_times(window));
+
+        let mut bbox = Aabb::default();
+        for time in times {
+            let (min, max) = self.corners(time);
+            bbox = Aabb::new_from_boxes(&bbox, &Aabb::new_from_points(min, max));
+        }
+
+        self.bbox = bbox;
+    }
+
+    /// The lowest and highest corners of the volume at time
+    fn corners(&self, time: f64) -> (Point3, Point3) {
+        let a = self.min_timeline.combine_and_compute(time);
+        let b = self.max_timeline.combine_and_compute(time);
+
+        (
+            Point3::new(a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2])),
+            Point3::new(a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])),
+        )
+    }
+
+    /// The most any point in the volume can scatter or absorb, the
+    /// tracking steps are sized by this
+    fn majorant(&self) -> f64 {
+        self.grid.max_density * self.density_scale
+    }
+
+    /// The part of the interval inside of the volume's box and the
+    /// box's corners at the ray's time
+    fn span(&self, r: &Ray, ray_t: &Interval) -> Option<(Interval, Point3, Point3)> {
+        let (min, max) = self.corners(r.time());
+        let mut span = ray_t.clone();
+        if !Aabb::new_from_points(min.clone(), max.clone()).hit(r, &mut span) {
+            return None;
+        }
+
+        Some((span, min, max))
+    }
+
+    /// The density and temperature at p
+    fn lookup(&self, p: &Point3, min: &Point3, max: &Point3) -> (f64, f64) {
+        let local = |p: f64, min: f64, max: f64| {
+            if max > min {
+                (p - min) / (max - min)
+            } else {
+                0.5
+            }
+        };
+        let local = [
+            local(p.x(), min.x(), max.x()),
+            local(p.y(), min.y(), max.y()),
+            local(p.z(), min.z(), max.z()),
+        ];
+
+        let (density, temperature) = self.grid.lookup(local);
+        (density * self.density_scale, temperature)
+    }
+
+    /// How far to the next tracking step, in units of t
+    fn step(&self, r: &Ray, random: &mut RayRandom) -> f64 {
+        -(1.0 - random.uniform()).ln() / (self.majorant() * r.direction().length())
+    }
+
+    fn seed(&self) -> u64 {
+        (self.id as u64) ^ self.density_scale.to_bits()
+    }
+
+    /// How much of the light along the ray gets through. Every step
+    /// takes away the share of the light a real collision there would
+    /// have, this is ratio tracking.
+    pub(crate) fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        if self.hide || self.majorant() <= 0.0 {
+            return 1.0;
+        }
+        let Some((span, min, max)) = self.span(r, ray_t) else {
+            return 1.0;
+        };
+
+        let mut random = RayRandom::new(r, self.seed());
+        let mut transmittance: f64 = 1.0;
+        let mut t = span.min();
+        loop {
+            t += self.step(r, &mut random);
+            if t >= span.max() || transmittance <= 0.0 {
+                return transmittance.max(0.0);
+            }
+
+            let (density, _) = self.lookup(&r.at(t), &min, &max);
+            transmittance *= 1.0 - density / self.majorant();
+        }
+    }
+}
+
+impl Hittable for VoxelVolume {
+    /// Delta tracking, a step is a real collision as often as the
+    /// density there is of the majorant. The others are skipped.
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        if self.hide || self.majorant() <= 0.0 {
+            return None;
+        }
+        let (span, min, max) = self.span(r, ray_t)?;
+
+        let mut random = RayRandom::new(r, self.seed());
+        let mut t = span.min();
+        loop {
+            t += self.step(r, &mut random);
+            if t >= span.max() {
+                return None;
+            }
+
+            let p = r.at(t);
+            let (density, temperature) = self.lookup(&p, &min, &max);
+            if random.uniform() * self.majorant() >= density {
+                continue;
+            }
+
+            // What is not scattered is absorbed, and absorbing is what
+            // a hot medium gives off light by
+            let mut phase = self.phase.clone();
+            if temperature > 0.0 && self.emission_scale > 0.0 {
+                let albedo = phase.albedo();
+                let absorbed = Radiance::new(1.0 - albedo.r(), 1.0 - albedo.g(), 1.0 - albedo.b());
+                phase.set_emission(absorbed * blackbody(temperature) * self.emission_scale);
+            }
+
+            // Media have no surface so the normal is arbitrary
+            let normal = Vec3::new(1.0, 0.0, 0.0);
+            // Safety: the normal has unit length
+            let rec = unsafe {
+                HitRecord::new(
+                    r,
+                    p,
+                    normal,
+                    t,
+                    0.0,
+                    0.0,
+                    Materials::HenyeyGreenstein(phase),
+                )
+            };
+            return Some(rec);
+        }
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+/// The light given off by something at kelvin degrees. Planck's law
+/// at a red, a green and a blue wavelength gives the color, which is
+/// scaled to a brightest channel of (kelvin / 1000)^4.
+fn blackbody(kelvin: f64) -> Radiance {
+    // The second radiation constant, hc / k, in meter Kelvin
+    const C2: f64 = 1.4388e-2;
+    let planck =
+        |wavelength: f64| 1.0 / (wavelength.powi(5) * ((C2 / (wavelength * kelvin)).exp() - 1.0));
+
+    let (r, g, b) = (planck(610e-9), planck(550e-9), planck(465e-9));
+    let brightest = r.max(g).max(b);
+    if kelvin <= 0.0 || !brightest.is_normal() {
+        return Radiance::black();
+    }
+
+    Radiance::new(r, g, b) * ((kelvin / 1000.0).powi(4) / brightest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        objects::bvh::Axis,
+        timeline::{InterpolationType, TransformSpace},
+    };
+
+    #[test]
+    fn ratio_tracking_agrees_with_delta_tracking() {
+        // Thick on one side and empty on the other
+        let density = (0..64)
+            .map(|index| if index % 4 < 2 { 2.0 } else { 0.0 })
+            .collect();
+        let grid = VoxelGrid::new([4, 4, 4], Voxels::Dense(density), None);
+        let min = Point3::new(-1.0, -1.0, -1.0);
+        let volume = VoxelVolume::new(Arc::new(grid), min.clone(), -min, Color::white(), 0.0);
+
+        let rays = 20_000;
+        let ray = |k: usize| {
+            Ray::new(Point3::new(-0.5, 0.3, 5.0), Vec3::new(0.0, 0.0, -1.0))
+                .with_medium_sample((k as f64 + 0.5) / rays as f64)
+        };
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        let through = (0..rays)
+            .filter(|&k| volume.hit(&ray(k), &ray_t).is_none())
+            .count() as f64
+            / rays as f64;
+        let ratio = (0..rays)
+            .map(|k| volume.transmittance(&ray(k), &ray_t))
+            .sum::<f64>()
+            / rays as f64;
+
+        // Two units through voxels of density 2
+        let expected = (-2.0f64 * 2.0).exp();
+        assert!((through - expected).abs() < 0.01);
+        assert!((ratio - expected).abs() < 0.01);
+
+        // The empty side lets everything through
+        let empty = Ray::new(Point3::new(0.8, 0.3, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(volume.transmittance(&empty, &ray_t), 1.0);
+    }
+
+    #[test]
+    fn hot_voxels_glow() {
+        let grid = |kelvin: f32| {
+            let grid = VoxelGrid::new(
+                [1, 1, 1],
+                Voxels::Dense(vec![1000.0]),
+                Some(Voxels::Dense(vec![kelvin])),
+            );
+            let min = Point3::new(-1.0, -1.0, -1.0);
+            VoxelVolume::new(Arc::new(grid), min.clone(), -min, Color::black(), 0.0)
+        };
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let glow = |volume: VoxelVolume| {
+            let rec = volume
+                .hit(&r, &Interval::new(0.001, f64::INFINITY))
+                .unwrap();
+            rec.material().emitted(&r, &rec)
+        };
+
+        assert_eq!(glow(grid(0.0)), Radiance::black());
+        // A black phase absorbs everything, so a 1000 Kelvin voxel
+        // glows with a brightest channel of 1. Fire is red.
+        let fire = glow(grid(1000.0));
+        assert!((fire.r() - 1.0).abs() < 1e-9);
+        assert!(fire.r() > fire.g() && fire.g() > fire.b());
+
+        // Hotter is brighter and whiter
+        let hot = blackbody(6500.0);
+        assert!(hot.max_channel() > fire.max_channel());
+        assert!(hot.b() / hot.r() > fire.b() / fire.r());
+    }
+
+    #[test]
+    fn keyed_corners_move_the_box() {
+        let grid = VoxelGrid::new([1, 1, 1], Voxels::Dense(vec![1000.0]), None);
+        let mut volume = VoxelVolume::new(
+            Arc::new(grid),
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+            Color::white(),
+            0.0,
+        );
+        let moves = [
+            (&mut volume.min_timeline, Point3::new(4.0, 0.0, 0.0)),
+            (&mut volume.max_timeline, Point3::new(5.0, 1.0, 1.0)),
+        ];
+        for (timeline, corner) in moves {
+            timeline.translate_point(corner, 1.0, InterpolationType::LERP, TransformSpace::World);
+        }
+        volume.update_bb(&Interval::new(0.0, 1.0));
+
+        let x = volume.bounding_box().axis_interval(Axis::X);
+        assert_eq!((x.min(), x.max()), (0.0, 5.0));
+
+        // The volume is only where its corners are at the ray's time
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let down = |x: f64, time: f64| {
+            Ray::new_at_time(Point3::new(x, 5.0, 0.5), Vec3::new(0.0, -1.0, 0.0), time)
+        };
+        assert!(volume.hit(&down(0.5, 0.0), &ray_t).is_some());
+        assert!(volume.hit(&down(0.5, 1.0), &ray_t).is_none());
+        assert!(volume.hit(&down(4.5, 1.0), &ray_t).is_some());
+    }
+}
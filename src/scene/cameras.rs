@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use crate::{
+    camera::{Camera, CropOutput, Integrators, StereoLayout},
+    scene::{ObjectType, Scene},
+    timeline::TransformTimeline,
+};
+
+/// From start on the camera with this id is the one that renders,
+/// until the next cut
+#[derive(Debug, Clone)]
+pub(super) struct Cut {
+    start: f64,
+    camera: usize,
+}
+
+/// The settings that the scene sets on all of its cameras at once.
+/// They are kept so cameras added later get them too.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SharedSettings {
+    pub(super) seed: Option<u64>,
+    pub(super) integrator: Option<Integrators>,
+    pub(super) crop: Option<(u32, u32, u32, u32, CropOutput)>,
+    pub(super) checkpoint_interval: Option<Duration>,
+    pub(super) resume: bool,
+    pub(super) stereo: Option<(f64, f64, StereoLayout)>,
+}
+
+impl SharedSettings {
+    fn apply(&self, cam: &mut Camera) {
+        if let Some(seed) = self.seed {
+            cam.set_seed(seed);
+        }
+        if let Some(integrator) = &self.integrator {
+            cam.set_integrator(integrator.clone());
+        }
+        if let Some((x0, y0, x1, y1, output)) = &self.crop {
+            cam.set_crop_window(*x0, *y0, *x1, *y1, output.clone());
+        }
+        if let Some((interocular, convergence, layout)) = &self.stereo {
+            cam.set_stereo(*interocular, *convergence, layout.clone());
+        }
+        cam.set_checkpoint_interval(self.checkpoint_interval);
+        cam.set_resume(self.resume);
+    }
+}
+
+/// This file has the bindings for scenes with more than one camera.
+/// The camera made with the scene has the alias 'cam'.
+impl Scene {
+    /// Adds a camera named {alias} and returns it so it can be set up. It starts with the
+    /// same image size, frame rate, shutter angle and thread count as the scene's camera,
+    /// along with the settings the scene gives every camera such as the seed.
+    ///
+    /// # Panics:
+    /// Panics if the alias is already used.
+    pub fn add_camera(&mut self, alias: &str) -> &mut Camera {
+        let Some(id) = self.id_vendor.vend_id(alias, ObjectType::Camera) else {
+            panic!(
+                "This camera's alias collides with another name in the scene! Try changing {alias} to a new name."
+            );
+        };
+
+        let mut cam = self.scene_cam.new_matching();
+        self.shared_settings.apply(&mut cam);
+        self.cameras.entry(id).or_insert(cam)
+    }
+
+    /// Returns the camera named {alias}, 'cam' is the scene's camera
+    ///
+    /// # Panics:
+    /// Panics if there is no camera named {alias}.
+    pub fn camera_mut(&mut self, alias: &str) -> &mut Camera {
+        let id = self.camera_id(alias);
+        self.camera_by_id(id)
+    }
+
+    /// Renders with the camera named {alias} whenever no cut says otherwise. This is 'cam'
+    /// until it is changed.
+    ///
+    /// # Panics:
+    /// Panics if there is no camera named {alias}.
+    pub fn set_active_camera(&mut self, alias: &str) {
+        self.active_camera = self.camera_id(alias);
+    }
+
+    /// Cuts to the camera named {alias} at time start in seconds. The camera renders until
+    /// the next cut, before the first cut the active camera renders. A cut at the same time
+    /// as another replaces it.
+    ///
+    /// # Panics:
+    /// Panics if there is no camera named {alias} or start is negative.
+    pub fn add_cut(&mut self, alias: &str, start: f64) {
+        assert!(
+            start >= 0.0,
+            "Cannot cut before the animation start. You tried to cut at: {start}"
+        );
+
+        let cut = Cut {
+            start,
+            camera: self.camera_id(alias),
+        };
+        match self.cuts.binary_search_by(|c| c.start.total_cmp(&start)) {
+            Ok(index) => self.cuts[index] = cut,
+            Err(index) => self.cuts.insert(index, cut),
+        }
+    }
+
+    /// Removes every cut so only the active camera renders
+    pub fn clear_cuts(&mut self) {
+        self.cuts.clear();
+    }
+
+    /// The id of the camera that renders the frame starting at time t
+    pub(super) fn camera_id_at(&self, t: f64) -> usize {
+        self.cuts
+            .iter()
+            // Frame times are sums of 1 / frame rate so they can land just short of a cut
+            .rfind(|cut| cut.start <= t + 1e-9)
+            .map_or(self.active_camera, |cut| cut.camera)
+    }
+
+    /// The camera that renders the current frame, the cuts pick it
+    /// from when the frame starts
+    pub(super) fn frame_camera_id(&self) -> usize {
+        self.camera_id_at(self.scene_cam.shutter_interval().min())
+    }
+
+    /// Every camera in the scene, starting with 'cam'
+    pub(super) fn cameras_mut(&mut self) -> impl Iterator<Item = &mut Camera> {
+        std::iter::once(&mut self.scene_cam).chain(self.cameras.values_mut())
+    }
+
+    fn camera_id(&self, alias: &str) -> usize {
+        match self.id_vendor.alias_lookup(alias) {
+            Some(info) if info.o_type == ObjectType::Camera => info.id,
+            _ => {
+                panic!("There is no camera named `{alias}`. Are you sure you typed the right name?")
+            }
+        }
+    }
+
+    pub(super) fn camera_by_id(&mut self, id: usize) -> &mut Camera {
+        if id == 0 {
+            &mut self.scene_cam
+        } else {
+            self.cameras
+                .get_mut(&id)
+                .expect("Every camera id has a camera")
+        }
+    }
+
+    /// Finds the timeline a camera alias such as 'from' or 'close.at'
+    /// keys. 'from' is where the camera is and 'at' is where it looks,
+    /// without a camera name in front they are on 'cam'.
+    pub(super) fn camera_target(&mut self, alias: &str) -> &mut TransformTimeline {
+        let (camera, target) = alias.rsplit_once('.').unwrap_or(("cam", alias));
+        let cam = self.camera_mut(camera);
+
+        match target {
+            "from" => &mut cam.look_from,
+            "at" => &mut cam.look_at,
+            _ => panic!("A camera can only move its 'from' or its 'at'. `{alias}` is invalid."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::{InterpolationType, TransformSpace};
+
+    #[test]
+    fn cuts_pick_cameras() {
+        let mut scene = Scene::new_movie(1.0, 8, 24, 180.0, 1, 3.0);
+        scene.add_camera("wide");
+        scene.add_camera("close");
+        let (wide, close) = (scene.camera_id("wide"), scene.camera_id("close"));
+
+        // Without cuts the active camera renders
+        assert_eq!(scene.camera_id_at(1.0), 0);
+        scene.set_active_camera("wide");
+        assert_eq!(scene.camera_id_at(1.0), wide);
+
+        scene.add_cut("close", 1.0);
+        scene.add_cut("cam", 2.0);
+        assert_eq!(scene.camera_id_at(0.5), wide);
+        assert_eq!(scene.camera_id_at(24.0 * (1.0 / 24.0)), close);
+        assert_eq!(scene.camera_id_at(2.5), 0);
+
+        scene.clear_cuts();
+        assert_eq!(scene.camera_id_at(2.5), wide);
+    }
+
+    #[test]
+    fn cameras_added_later_match() {
+        let mut scene = Scene::new_movie(1.0, 8, 24, 180.0, 1, 3.0);
+        scene.set_resume(true);
+        scene.add_camera("close");
+        assert!(scene.camera_mut("close").resume());
+
+        // Named cameras are keyed through their name
+        let world = TransformSpace::World;
+        scene.cam_translate_x(2.0, 1.0, InterpolationType::LERP, world, "close.from");
+        let moved = |scene: &mut Scene, alias| scene.camera_mut(alias).get_from(1.0).x();
+        assert_eq!(moved(&mut scene, "close"), 2.0);
+        assert_eq!(moved(&mut scene, "cam"), 0.0);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    lights::Lights,
+    scene::{ObjectType, Scene},
+    timeline::{InterpolationType, TransformSpace, TransformTimeline},
+    utils::Point3,
+};
+
+/// This file has the bindings for point, spot and sun lights. They
+/// move with the same translate functions as objects, see
+/// scene_animator. Emissive objects and the sky light the scene on
+/// their own.
+impl Scene {
+    /// Adds a point, spot or sun light to the scene with a name of {alias}
+    ///
+    /// # Panics:
+    /// Panics if the alias is already used or the light is an area or environment light,
+    /// the scene finds those by itself.
+    pub fn add_light(&mut self, mut light: Lights, alias: &str) {
+        assert!(
+            !matches!(light, Lights::Area(_) | Lights::Environment(_)),
+            "Area and environment lights come from emissive materials and the skybox, they cannot be added"
+        );
+        let Some(id) = self.id_vendor.vend_id(alias, ObjectType::Light) else {
+            panic!(
+                "This light's alias collides with another name in the scene! Try changing {alias} to a new name."
+            );
+        };
+
+        light.set_id(id);
+        self.lights.push(light);
+    }
+
+    /// Points the spot light named {alias} at target from keyframe on. Moving the light
+    /// with translate keeps it pointed the same way.
+    ///
+    /// # Panics:
+    /// Panics if there is no spot light named {alias}.
+    pub fn aim_light(&mut self, target: Point3, keyframe: f64, it: InterpolationType, alias: &str) {
+        let id = self.light_id(alias);
+
+        let spot = self.lights.iter_mut().find_map(|light| match light {
+            Lights::Spot(s) if s.id == id => Some(s),
+            _ => None,
+        });
+        let Some(spot) = spot else {
+            panic!("The light `{alias}` is not a spot light, only spot lights can be aimed.");
+        };
+
+        spot.target_timeline
+            .translate_point(target, keyframe, it, TransformSpace::World);
+    }
+
+    /// The timelines of the light with this id, empty if the id is
+    /// not a light
+    pub(super) fn light_timelines_mut(&mut self, id: usize) -> Vec<&mut TransformTimeline> {
+        self.lights
+            .iter_mut()
+            .filter(|light| light.id() == Some(id))
+            .flat_map(|light| light.timelines_mut())
+            .collect()
+    }
+
+    fn light_id(&self, alias: &str) -> usize {
+        match self.id_vendor.alias_lookup(alias) {
+            Some(info) if info.o_type == ObjectType::Light => info.id,
+            _ => {
+                panic!("There is no light named `{alias}`. Are you sure you typed the right name?")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lights::{Light, punctual::SunLight},
+        utils::{Radiance, Vec3},
+    };
+
+    #[test]
+    fn keyed_sun_moves() {
+        let mut scene = Scene::new_movie(1.0, 8, 24, 180.0, 1, 3.0);
+        let sun = SunLight::new(Vec3::new(1.0, 0.0, 0.0), Radiance::new(3.0, 3.0, 3.0), 0.0);
+        scene.add_light(Lights::Sun(sun), "sun");
+
+        // Rise to the top of the sky, then set on the other side
+        scene.translate_point(
+            Point3::new(0.0, 1.0, 0.0),
+            1.0,
+            InterpolationType::LERP,
+            TransformSpace::World,
+            "sun",
+        );
+        scene.translate_point(
+            Point3::new(-1.0, 0.0, 0.0),
+            2.0,
+            InterpolationType::LERP,
+            TransformSpace::World,
+            "sun",
+        );
+
+        let Lights::Sun(sun) = &scene.lights[0] else {
+            unreachable!()
+        };
+        let towards = |t: f64| {
+            sun.sample(&Point3::origin(), t, (0.5, 0.5))
+                .unwrap()
+                .direction
+        };
+        assert!((towards(0.0) - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((towards(1.0) - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((towards(2.0) - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+
+        scene.hide_element("sun");
+        assert!(scene.lights[0].hidden());
+    }
+}
@@ -1,15 +1,24 @@
-use std::fs;
+use std::{collections::HashMap, f64::consts::PI, fs, path::Path, sync::Arc, time::Duration};
 
 use crate::{
     asset_loader::{self, img_loader::RTWImage},
-    camera::Camera,
-    materials::Materials,
-    objects::{Hittables, bvhwrapper::BVHWrapper, hitlist::HitList},
-    scene::id_vendor::IdVendor,
-    utils::{Color, Interval, Point3},
+    camera::{Camera, CropOutput, Integrators, OutputFormat, StereoLayout, checkpoint_path},
+    lights::{LightList, Lights},
+    materials::{Materials, lambertian::Lambertian},
+    objects::{
+        Hittables, bvhwrapper::BVHWrapper, constant_medium::ConstantMedium, hitlist::HitList,
+        sphere::Sphere,
+    },
+    scene::{
+        cameras::{Cut, SharedSettings},
+        id_vendor::IdVendor,
+    },
+    utils::{Color, Interval, Point3, Radiance, Vec3},
 };
 
+mod cameras;
 mod id_vendor;
+mod lights;
 mod movie_maker;
 mod scene_animator;
 
@@ -21,11 +30,38 @@ pub enum Skybox {
     //Planar(SkyboxImage),
     //Triplanar(SkyboxImage),
     //CameraMapping(SkyboxImage),
+    /// The same light from every direction, black for scenes only
+    /// lit by emissive materials
+    Solid(Radiance),
     Default,
 }
 
-/// TODO: Maybe make get_color a method on a skybox and it
-/// just computes it for the camera?
+impl Skybox {
+    /// The light coming from the sky in direction
+    pub fn radiance(&self, direction: &Vec3) -> Radiance {
+        match self {
+            Skybox::Spherical(sky) => {
+                let unit_direction = direction.clone().unit_vector();
+                let theta = unit_direction.x().atan2(unit_direction.z());
+                let phi = unit_direction.y().asin();
+
+                let u = (theta / (2.0 * PI)) + 0.5;
+                let v = (phi / PI) + 0.5;
+
+                // Clamp then scale with the skyboxes size:
+                sky.get_radiance(u, v)
+            }
+            Skybox::Solid(radiance) => radiance.clone(),
+            Skybox::Default => {
+                let unit_direction = direction.clone().unit_vector();
+                let a = 0.5 * (unit_direction.y() + 1.0);
+
+                Radiance::from((1.0 - a) * Color::white() + a * Color::new(0.5, 0.7, 1.0))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SkyboxImage {
     image: RTWImage,
@@ -33,24 +69,32 @@ pub struct SkyboxImage {
 
 impl SkyboxImage {
     /// Take the uv coordinate mapping and convert it to
-    /// pixel mapping in the skybox image.
-    pub fn get_color(&self, u: f64, v: f64) -> Color {
+    /// pixel mapping in the skybox image. HDR skies keep
+    /// their full range.
+    pub fn get_radiance(&self, u: f64, v: f64) -> Radiance {
         let u = Interval::new(0.0, 1.0).clamp(u);
         let v = 1.0 - Interval::new(0.0, 1.0).clamp(v);
 
         let i = (u * self.image.width() as f64) as usize;
         let j = (v * self.image.height() as f64) as usize;
 
-        self.image.pixel_data(i, j)
+        self.image.pixel_radiance(i, j)
+    }
+
+    pub(crate) fn image(&self) -> &RTWImage {
+        &self.image
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ObjectType {
     Camera,
+    Light,
+    Medium,
     Sphere,
     TriangleMesh,
     Triangle,
+    Volume,
 }
 
 /// This struct keeps track of information about objects in the scene
@@ -73,12 +117,22 @@ impl ObjectInfo {
 /// for film making purposes. WARNING: Do not directly call
 /// render unless you know what you are doing.
 pub struct Scene {
+    /// The camera with the alias 'cam'
     pub scene_cam: Camera,
+    /// Every other camera by id
+    cameras: HashMap<usize, Camera>,
+    active_camera: usize,
+    cuts: Vec<Cut>,
+    shared_settings: SharedSettings,
     elements: HitList,
+    /// Point, spot and sun lights
+    lights: Vec<Lights>,
+    fog: Option<ConstantMedium>,
     skybox: Skybox,
     id_vendor: IdVendor,
     duration: Option<f64>,
     frame_rate: usize,
+    output_format: OutputFormat,
 }
 
 impl Scene {
@@ -104,11 +158,18 @@ impl Scene {
 
         Scene {
             scene_cam,
+            cameras: HashMap::new(),
+            active_camera: 0,
+            cuts: Vec::new(),
+            shared_settings: SharedSettings::default(),
             elements,
+            lights: Vec::new(),
+            fog: None,
             skybox,
             id_vendor: IdVendor::new(),
             duration: None,
             frame_rate,
+            output_format: OutputFormat::Ppm,
         }
     }
 
@@ -135,14 +196,88 @@ impl Scene {
 
         Scene {
             scene_cam,
+            cameras: HashMap::new(),
+            active_camera: 0,
+            cuts: Vec::new(),
+            shared_settings: SharedSettings::default(),
             elements,
+            lights: Vec::new(),
+            fog: None,
             skybox,
             id_vendor: IdVendor::new(),
             duration: Some(duration),
             frame_rate,
+            output_format: OutputFormat::Ppm,
         }
     }
 
+    /// Sets the format images are saved in. This is used when the
+    /// name passed to render_scene has no extension. For movies this
+    /// is the format of each frame in the artifacts folder.
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output_format = format;
+    }
+
+    /// Sets the seed the render's random numbers come from. A scene
+    /// renders the same image every time with the same seed and
+    /// resolution, whatever the thread count. This applies to every
+    /// camera, cameras added later get it too.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.shared_settings.seed = Some(seed);
+        self.cameras_mut().for_each(|cam| cam.set_seed(seed));
+    }
+
+    /// Sets how the cameras turn rays into light, see
+    /// `Camera::set_integrator`. This applies to every camera, cameras
+    /// added later get it too.
+    pub fn set_integrator(&mut self, integrator: Integrators) {
+        self.shared_settings.integrator = Some(integrator.clone());
+        self.cameras_mut()
+            .for_each(|cam| cam.set_integrator(integrator.clone()));
+    }
+
+    /// Only renders the pixels from (x0, y0) up to (x1, y1), see
+    /// `Camera::set_crop_window`. For movies every frame is cropped.
+    /// This applies to every camera, cameras added later get it too.
+    pub fn set_crop_window(&mut self, x0: u32, y0: u32, x1: u32, y1: u32, output: CropOutput) {
+        self.shared_settings.crop = Some((x0, y0, x1, y1, output.clone()));
+        self.cameras_mut()
+            .for_each(|cam| cam.set_crop_window(x0, y0, x1, y1, output.clone()));
+    }
+
+    /// Saves a checkpoint of the image being rendered every interval,
+    /// None turns checkpoints off. See `Scene::set_resume`. This
+    /// applies to every camera, cameras added later get it too.
+    pub fn set_checkpoint_interval(&mut self, interval: Option<Duration>) {
+        self.shared_settings.checkpoint_interval = interval;
+        self.cameras_mut()
+            .for_each(|cam| cam.set_checkpoint_interval(interval));
+    }
+
+    /// Continues a stopped render instead of starting over. Images
+    /// resume from their checkpoint and movies also skip the frames
+    /// that are already in the artifacts folder. This applies to
+    /// every camera, cameras added later get it too.
+    pub fn set_resume(&mut self, resume: bool) {
+        self.shared_settings.resume = resume;
+        self.cameras_mut().for_each(|cam| cam.set_resume(resume));
+    }
+
+    /// Renders a left and a right eye, see `Camera::set_stereo`. This
+    /// applies to every camera, cameras added later get it too. Movies
+    /// with separate files get a movie_left.mp4 and a movie_right.mp4.
+    pub fn set_stereo(&mut self, interocular: f64, convergence: f64, layout: StereoLayout) {
+        self.shared_settings.stereo = Some((interocular, convergence, layout.clone()));
+        self.cameras_mut()
+            .for_each(|cam| cam.set_stereo(interocular, convergence, layout.clone()));
+    }
+
+    /// Sets the skybox to one radiance in every direction. Use
+    /// Radiance::black() for scenes that are only lit by lights.
+    pub fn load_solid_skybox(&mut self, radiance: Radiance) {
+        self.skybox = Skybox::Solid(radiance);
+    }
+
     /// Sets the skybox to the default LERP between white
     /// and blue
     pub fn load_default_skybox(&mut self) {
@@ -155,6 +290,30 @@ impl Scene {
         self.skybox = Skybox::Spherical(SkyboxImage { image });
     }
 
+    /// Fills the scene with fog out to radius from the origin. Density,
+    /// albedo and g work like they do for a ConstantMedium. The sky and
+    /// lights past the fog still shine through it, which gives shafts
+    /// of light behind objects in the way.
+    ///
+    /// # Panics
+    /// Panics if the radius is not positive.
+    pub fn set_fog(&mut self, density: f64, albedo: Color, g: f64, radius: f64) {
+        assert!(
+            radius > 0.0,
+            "The fog's radius must be positive. You passed {radius}"
+        );
+
+        // The boundary is never seen so its material does not matter
+        let unseen = Materials::Lambertian(Lambertian::new_from_color(Color::black(), 1.0));
+        let boundary = Hittables::Sphere(Sphere::new(Point3::origin(), radius, unseen));
+        self.fog = Some(ConstantMedium::new(boundary, density, albedo, g));
+    }
+
+    /// Removes the fog added with set_fog
+    pub fn clear_fog(&mut self) {
+        self.fog = None;
+    }
+
     /// Adds an element to the scene with a name of {alias}
     pub fn add_element(&mut self, element: Hittables, alias: &str) {
         match element {
@@ -184,6 +343,24 @@ impl Scene {
                 t.id = internal_id.unwrap();
                 self.elements.add(Hittables::Triangle(t));
             }
+            Hittables::ConstantMedium(mut m) => {
+                let Some(id) = self.id_vendor.vend_id(alias, ObjectType::Medium) else {
+                    panic!(
+                        "This medium's alias collides with another name in the scene! Try changing {alias} to a new name."
+                    );
+                };
+                m.id = id;
+                self.elements.add(Hittables::ConstantMedium(m));
+            }
+            Hittables::VoxelVolume(mut v) => {
+                let Some(id) = self.id_vendor.vend_id(alias, ObjectType::Volume) else {
+                    panic!(
+                        "This volume's alias collides with another name in the scene! Try changing {alias} to a new name."
+                    );
+                };
+                v.id = id;
+                self.elements.add(Hittables::VoxelVolume(v));
+            }
         }
     }
 
@@ -225,6 +402,10 @@ impl Scene {
                     t.id = internal_id.unwrap();
                     self.elements.add(Hittables::Triangle(t));
                 }
+                // Meshes are only made of triangles
+                Hittables::ConstantMedium(_) | Hittables::VoxelVolume(_) => {
+                    self.elements.add(element);
+                }
             }
         }
     }
@@ -252,6 +433,12 @@ impl Scene {
 
         let internal_id = internal_id.unwrap().id;
 
+        for light in self.lights.iter_mut() {
+            if light.id() == Some(internal_id) {
+                light.set_hidden(hide);
+            }
+        }
+
         for element in self.elements.get_objs().clone() {
             // Check if the element has the internal id
             let updated = match element {
@@ -270,6 +457,18 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(mut m) => {
+                    if m.id == internal_id {
+                        m.hide = hide
+                    }
+                    Hittables::ConstantMedium(m)
+                }
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == internal_id {
+                        v.hide = hide
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
@@ -280,11 +479,29 @@ impl Scene {
     /// Render scene wraps the HitList before rendering
     /// Scenes keep this unwrapped before rendering for
     /// easy alteration when working with movie type renders
+    ///
+    /// If fname ends in a supported extension such as .png or .exr
+    /// the image is saved in that format, otherwise the scene's output
+    /// format is used and its extension is added for you.
     pub fn render_scene(&mut self, fname: &str) {
+        let (fname, format) = match OutputFormat::from_path(fname) {
+            Some(format) => {
+                let base = &fname[..fname.len() - format.extension().len() - 1];
+
+                // Keep the scene's format if it only differs in bit depth
+                if format.extension() == self.output_format.extension() {
+                    (base, self.output_format.clone())
+                } else {
+                    (base, format)
+                }
+            }
+            None => (fname, self.output_format.clone()),
+        };
+
         if self.duration.is_some() {
-            self.render_movie(fname);
+            self.render_movie(fname, &format);
         } else {
-            self.render_image(fname);
+            self.render_image(fname, &format);
         }
     }
 
@@ -292,10 +509,23 @@ impl Scene {
     /// called artifacts. This will store an image for each frame. TODO: add a delete artifacts option.
     /// After rendering each image, this will use the ffmpeg bindings in rust to put together a video with
     /// the framerate specified by the scene. TODO: Add slow and fast motion keyframing to the scene
-    fn render_movie(&mut self, fname: &str) {
-        fs::create_dir(fname).expect("Cannot make the movie directory");
-        fs::create_dir(fname.to_owned() + "/artifacts")
-            .expect("Failed to create the artifacts subdirectory");
+    ///
+    /// When resuming, the directory may already exist and frames that were
+    /// finished are not rendered again.
+    fn render_movie(&mut self, fname: &str, format: &OutputFormat) {
+        let resume = self.shared_settings.resume;
+        // The cameras share the image size, the first frame's camera
+        // decides which movies are made
+        let first_cam = self.frame_camera_id();
+        let suffixes = self.camera_by_id(first_cam).output_suffixes();
+        if resume {
+            fs::create_dir_all(fname.to_owned() + "/artifacts")
+                .expect("Failed to create the artifacts subdirectory");
+        } else {
+            fs::create_dir(fname).expect("Cannot make the movie directory");
+            fs::create_dir(fname.to_owned() + "/artifacts")
+                .expect("Failed to create the artifacts subdirectory");
+        }
 
         let frames = self.compute_frame_count();
         let digit_count = frames.to_string().len();
@@ -308,15 +538,24 @@ impl Scene {
             let image_num = format!("{frame:0>digit_count$}");
             let out_name = fname.to_owned() + "/artifacts/image" + &image_num;
 
-            self.render_image(&out_name);
-            self.scene_cam.next_frame();
+            // Only the camera that renders a frame knows its files
+            let cam = self.frame_camera_id();
+            let cam = self.camera_by_id(cam);
+            let skip = cam.resume() && frame_finished(&out_name, format, &cam.output_suffixes());
+            if !skip {
+                self.render_image(&out_name, format);
+            }
+            self.cameras_mut().for_each(|cam| cam.next_frame());
 
             pb.set_message(format!("img{}", frame + 1));
             pb.inc(1);
         }
 
-        let res = self.scene_cam.get_res();
-        movie_maker::make_mp4(res, self.frame_rate, digit_count, fname);
+        // Stereo movies with separate files get a movie for each eye
+        let res = self.camera_by_id(first_cam).get_res();
+        for suffix in suffixes {
+            movie_maker::make_mp4(res, self.frame_rate, digit_count, fname, suffix, format);
+        }
         // cleanup artifacts TODO
         // or perhaps zip it?
     }
@@ -329,16 +568,36 @@ impl Scene {
         (self.duration.unwrap() * self.frame_rate as f64).ceil() as usize
     }
 
-    fn render_image(&mut self, fname: &str) {
-        let world = BVHWrapper::new_wrapper(self.elements.clone());
+    fn render_image(&mut self, fname: &str, format: &OutputFormat) {
+        // The cuts pick the camera from when the frame starts
+        let cam_id = self.frame_camera_id();
+
+        // Size the boxes for everywhere the objects move while the shutter
+        // is open, this way the threads never have to change the world
+        let shutter = self.camera_by_id(cam_id).shutter_interval();
+        let mut elements = self.elements.clone();
+        elements.update_bb(&shutter);
+
+        let lights = LightList::new(elements.get_objs(), &self.lights, &self.skybox);
+        let mut world = BVHWrapper::new_wrapper(elements);
+        // The fog covers everything so it stays out of the BVH
+        if let Some(fog) = &self.fog {
+            let fog = Hittables::ConstantMedium(fog.clone());
+            world = Hittables::HitList(HitList::new(vec![world, fog]));
+        }
+        let world = Arc::new(world);
+
+        let out_name = fname.to_owned() + "." + format.extension();
+
+        let cam = match cam_id {
+            0 => &mut self.scene_cam,
+            id => self.cameras.get_mut(&id).unwrap(),
+        };
 
         // Get rid of the prints soon
-        match self
-            .scene_cam
-            .render(&self.skybox, &world, &(fname.to_owned() + ".ppm"))
-        {
+        match cam.render(&self.skybox, world, &lights, &out_name, format) {
             Ok(()) => {
-                eprintln!("Successful render! Image stored at: {fname}.ppm");
+                eprintln!("Successful render! Image stored at: {out_name}");
             }
             Err(e) => {
                 eprintln!("Render failed. {e}");
@@ -346,3 +605,15 @@ impl Scene {
         }
     }
 }
+
+/// A frame is finished once its image has been written and its
+/// checkpoint removed. An empty image was stopped while it was
+/// being written.
+fn frame_finished(out_name: &str, format: &OutputFormat, suffixes: &[&str]) -> bool {
+    suffixes.iter().all(|suffix| {
+        let image = out_name.to_owned() + suffix + "." + format.extension();
+        let written = fs::metadata(&image).is_ok_and(|m| m.len() > 0);
+
+        written && !Path::new(&checkpoint_path(&image)).exists()
+    })
+}
@@ -1,11 +1,24 @@
 use std::process::{Command, Stdio};
 
+use crate::camera::OutputFormat;
+
 /// Looks for images in <fname>/artifacts and loads
-/// all the ppms. Then uses ffmpeg to build an mp4 video
+/// all the frames in the given format. Then uses ffmpeg
+/// to build an mp4 video. suffix picks the frames whose
+/// names end in it, such as the _left eye of a stereo movie
 /// TODO: use the ffmpeg crate if we need more power
-pub fn make_mp4(_res: (usize, usize), frame_rate: usize, padding: usize, fname: &str) {
-    let output_path = fname.to_owned() + "/movie.mp4";
-    let image_pattern = fname.to_owned() + &format!("/artifacts/image%0{padding}d.ppm");
+pub fn make_mp4(
+    _res: (usize, usize),
+    frame_rate: usize,
+    padding: usize,
+    fname: &str,
+    suffix: &str,
+    format: &OutputFormat,
+) {
+    let output_path = fname.to_owned() + &format!("/movie{suffix}.mp4");
+    let extension = format.extension();
+    let image_pattern =
+        fname.to_owned() + &format!("/artifacts/image%0{padding}d{suffix}.{extension}");
     let frame_rate = frame_rate.to_string();
 
     Command::new("ffmpeg")
@@ -2,7 +2,7 @@ use crate::{
     objects::{Hittables, hitlist::HitList},
     scene::{ObjectInfo, ObjectType, Scene},
     timeline::{InterpolationType, TransformSpace},
-    utils::Point3,
+    utils::{Point3, Vec3},
 };
 
 /// This file has all the bindings for animating a scene.
@@ -31,14 +31,17 @@ impl Scene {
     }
     // Scaling functions:
 
-    /// Scales a scene object's x-value, this is not valid on spheres
+    /// Scales a scene object's x-value, this is not valid on spheres, media or lights
     ///
     /// # Panic
-    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere
+    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere, a medium or a light
     pub fn scale_x(&mut self, x: f64, keyframe: f64, it: InterpolationType, alias: &str) {
-        let invalid_types = [ObjectType::Sphere];
-        let alias_info =
-            self.check_and_get_alias(alias, &invalid_types, "ScaleX cannot apply to Spheres");
+        let invalid_types = [ObjectType::Sphere, ObjectType::Medium, ObjectType::Light];
+        let alias_info = self.check_and_get_alias(
+            alias,
+            &invalid_types,
+            "ScaleX cannot apply to Spheres, media or lights",
+        );
 
         // Everything is okay, find the object and add the transformation:
         let mut updated_list = HitList::default();
@@ -58,6 +61,14 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(_) => element,
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        v.min_timeline.scale_x(x, keyframe, it.clone());
+                        v.max_timeline.scale_x(x, keyframe, it.clone());
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
@@ -65,14 +76,17 @@ impl Scene {
         self.elements = updated_list;
     }
 
-    /// Scales a scene object's y-value, this is not valid on spheres
+    /// Scales a scene object's y-value, this is not valid on spheres, media or lights
     ///
     /// # Panic
-    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere
+    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere, a medium or a light
     pub fn scale_y(&mut self, y: f64, keyframe: f64, it: InterpolationType, alias: &str) {
-        let invalid_types = [ObjectType::Sphere];
-        let alias_info =
-            self.check_and_get_alias(alias, &invalid_types, "ScaleY cannot apply to Spheres");
+        let invalid_types = [ObjectType::Sphere, ObjectType::Medium, ObjectType::Light];
+        let alias_info = self.check_and_get_alias(
+            alias,
+            &invalid_types,
+            "ScaleY cannot apply to Spheres, media or lights",
+        );
 
         // Everything is okay, find the object and add the transformation:
         let mut updated_list = HitList::default();
@@ -92,6 +106,14 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(_) => element,
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        v.min_timeline.scale_y(y, keyframe, it.clone());
+                        v.max_timeline.scale_y(y, keyframe, it.clone());
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
@@ -99,14 +121,17 @@ impl Scene {
         self.elements = updated_list;
     }
 
-    /// Scales a scene object's z-value, this is not valid on spheres
+    /// Scales a scene object's z-value, this is not valid on spheres, media or lights
     ///
     /// # Panic
-    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere
+    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere, a medium or a light
     pub fn scale_z(&mut self, z: f64, keyframe: f64, it: InterpolationType, alias: &str) {
-        let invalid_types = [ObjectType::Sphere];
-        let alias_info =
-            self.check_and_get_alias(alias, &invalid_types, "ScaleZ cannot apply to Spheres");
+        let invalid_types = [ObjectType::Sphere, ObjectType::Medium, ObjectType::Light];
+        let alias_info = self.check_and_get_alias(
+            alias,
+            &invalid_types,
+            "ScaleZ cannot apply to Spheres, media or lights",
+        );
 
         // Everything is okay, find the object and add the transformation:
         let mut updated_list = HitList::default();
@@ -126,6 +151,14 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(_) => element,
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        v.min_timeline.scale_z(z, keyframe, it.clone());
+                        v.max_timeline.scale_z(z, keyframe, it.clone());
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
@@ -140,6 +173,9 @@ impl Scene {
     pub fn scale_r(&mut self, r: f64, keyframe: f64, it: InterpolationType, alias: &str) {
         let invalid_types = [
             ObjectType::Camera,
+            ObjectType::Light,
+            ObjectType::Medium,
+            ObjectType::Volume,
             ObjectType::Triangle,
             ObjectType::TriangleMesh,
         ];
@@ -165,6 +201,8 @@ impl Scene {
                     Hittables::Sphere(s)
                 }
                 Hittables::Triangle(_) => element,
+                Hittables::ConstantMedium(_) => element,
+                Hittables::VoxelVolume(_) => element,
             };
             updated_list.add(updated);
         }
@@ -177,11 +215,14 @@ impl Scene {
     /// use the individual axis scale functions.
     ///
     /// # Panic
-    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere
+    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere, a medium or a light
     pub fn scale_point(&mut self, p: Point3, keyframe: f64, it: InterpolationType, alias: &str) {
-        let invalid_types = [ObjectType::Sphere];
-        let alias_info =
-            self.check_and_get_alias(alias, &invalid_types, "ScaleAll cannot apply to Spheres");
+        let invalid_types = [ObjectType::Sphere, ObjectType::Medium, ObjectType::Light];
+        let alias_info = self.check_and_get_alias(
+            alias,
+            &invalid_types,
+            "ScaleAll cannot apply to Spheres, media or lights",
+        );
 
         // Everything is okay, find the object and add the transformation:
         let mut updated_list = HitList::default();
@@ -201,6 +242,14 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(_) => element,
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        v.min_timeline.scale_point(p.clone(), keyframe, it.clone());
+                        v.max_timeline.scale_point(p.clone(), keyframe, it.clone());
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
@@ -213,7 +262,7 @@ impl Scene {
     /// use the individual axis scale functions.
     ///
     /// # Panic
-    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere
+    /// Panics if the alias does not have an underlying object. Panics if the object underlying the alias is a sphere, a medium or a light
     pub fn scale_all_uniform(&mut self, v: f64, keyframe: f64, it: InterpolationType, alias: &str) {
         self.scale_point(Point3::new(v, v, v), keyframe, it, alias);
     }
@@ -270,11 +319,31 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(mut m) => {
+                    if m.id == alias_info.id {
+                        for timeline in m.timelines_mut() {
+                            timeline.translate_x(x, keyframe, it.clone(), space.clone());
+                        }
+                    }
+                    Hittables::ConstantMedium(m)
+                }
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        for timeline in [&mut v.min_timeline, &mut v.max_timeline] {
+                            timeline.translate_x(x, keyframe, it.clone(), space.clone());
+                        }
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
 
         self.elements = updated_list;
+
+        for timeline in self.light_timelines_mut(alias_info.id) {
+            timeline.translate_x(x, keyframe, it.clone(), space.clone());
+        }
     }
 
     /// Translates a scene object's x-value, this is valid on all types
@@ -323,11 +392,31 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(mut m) => {
+                    if m.id == alias_info.id {
+                        for timeline in m.timelines_mut() {
+                            timeline.translate_y(y, keyframe, it.clone(), space.clone());
+                        }
+                    }
+                    Hittables::ConstantMedium(m)
+                }
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        for timeline in [&mut v.min_timeline, &mut v.max_timeline] {
+                            timeline.translate_y(y, keyframe, it.clone(), space.clone());
+                        }
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
 
         self.elements = updated_list;
+
+        for timeline in self.light_timelines_mut(alias_info.id) {
+            timeline.translate_y(y, keyframe, it.clone(), space.clone());
+        }
     }
 
     /// Translates a scene object's z-value, this is valid on all types
@@ -376,11 +465,31 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(mut m) => {
+                    if m.id == alias_info.id {
+                        for timeline in m.timelines_mut() {
+                            timeline.translate_z(z, keyframe, it.clone(), space.clone());
+                        }
+                    }
+                    Hittables::ConstantMedium(m)
+                }
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        for timeline in [&mut v.min_timeline, &mut v.max_timeline] {
+                            timeline.translate_z(z, keyframe, it.clone(), space.clone());
+                        }
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
 
         self.elements = updated_list;
+
+        for timeline in self.light_timelines_mut(alias_info.id) {
+            timeline.translate_z(z, keyframe, it.clone(), space.clone());
+        }
     }
 
     /// Translates a scene objects position based on a point, this is valid on all types
@@ -441,22 +550,53 @@ impl Scene {
                     }
                     Hittables::Triangle(t)
                 }
+                Hittables::ConstantMedium(mut m) => {
+                    if m.id == alias_info.id {
+                        for timeline in m.timelines_mut() {
+                            timeline.translate_point(
+                                p.clone(),
+                                keyframe,
+                                it.clone(),
+                                space.clone(),
+                            );
+                        }
+                    }
+                    Hittables::ConstantMedium(m)
+                }
+                Hittables::VoxelVolume(mut v) => {
+                    if v.id == alias_info.id {
+                        for timeline in [&mut v.min_timeline, &mut v.max_timeline] {
+                            timeline.translate_point(
+                                p.clone(),
+                                keyframe,
+                                it.clone(),
+                                space.clone(),
+                            );
+                        }
+                    }
+                    Hittables::VoxelVolume(v)
+                }
             };
             updated_list.add(updated);
         }
 
         self.elements = updated_list;
+
+        for timeline in self.light_timelines_mut(alias_info.id) {
+            timeline.translate_point(p.clone(), keyframe, it.clone(), space.clone());
+        }
     }
 
     // Camera operations
 
-    /// Translates the camera's x location, alias of 'from' for the camera location, 'at' for
-    /// where the ray is cast. Note if you call the cam's set_from or set_at functions
-    /// from the scene directly the transform will be lost and any camera animations will have to
-    /// be replaced.
+    /// Translates a camera's x location, alias of 'from' for the camera location, 'at' for
+    /// where the ray is cast. These move 'cam', put a camera's name in front such as
+    /// 'close.from' to move another camera. Note if you call the cam's set_from or set_at
+    /// functions from the scene directly the transform will be lost and any camera
+    /// animations will have to be replaced.
     ///
     /// # Panic
-    /// Panics if the alias is not 'from' or 'at'.
+    /// Panics if the alias is not 'from' or 'at' of a camera in the scene.
     pub fn cam_translate_x(
         &mut self,
         x: f64,
@@ -465,22 +605,18 @@ impl Scene {
         space: TransformSpace,
         alias: &str,
     ) {
-        assert!(alias == "from" || alias == "at");
-
-        if alias == "from" {
-            self.scene_cam.look_from.translate_x(x, keyframe, it, space);
-        } else {
-            self.scene_cam.look_at.translate_x(x, keyframe, it, space);
-        }
+        self.camera_target(alias)
+            .translate_x(x, keyframe, it, space);
     }
 
-    /// Translates the camera's y location, alias of 'from' for the camera location, 'at' for
-    /// where the ray is cast. Note if you call the cam's set_from or set_at functions
-    /// from the scene directly the transform will be lost and any camera animations will have to
-    /// be replaced.
+    /// Translates a camera's y location, alias of 'from' for the camera location, 'at' for
+    /// where the ray is cast. These move 'cam', put a camera's name in front such as
+    /// 'close.from' to move another camera. Note if you call the cam's set_from or set_at
+    /// functions from the scene directly the transform will be lost and any camera
+    /// animations will have to be replaced.
     ///
     /// # Panic
-    /// Panics if the alias is not 'from' or 'at'.
+    /// Panics if the alias is not 'from' or 'at' of a camera in the scene.
     pub fn cam_translate_y(
         &mut self,
         y: f64,
@@ -489,22 +625,18 @@ impl Scene {
         space: TransformSpace,
         alias: &str,
     ) {
-        assert!(alias == "from" || alias == "at");
-
-        if alias == "from" {
-            self.scene_cam.look_from.translate_y(y, keyframe, it, space);
-        } else {
-            self.scene_cam.look_at.translate_y(y, keyframe, it, space);
-        }
+        self.camera_target(alias)
+            .translate_y(y, keyframe, it, space);
     }
 
-    /// Translates the camera's z location, alias of 'from' for the camera location, 'at' for
-    /// where the ray is cast. Note if you call the cam's set_from or set_at functions
-    /// from the scene directly the transform will be lost and any camera animations will have to
-    /// be replaced.
+    /// Translates a camera's z location, alias of 'from' for the camera location, 'at' for
+    /// where the ray is cast. These move 'cam', put a camera's name in front such as
+    /// 'close.from' to move another camera. Note if you call the cam's set_from or set_at
+    /// functions from the scene directly the transform will be lost and any camera
+    /// animations will have to be replaced.
     ///
     /// # Panic
-    /// Panics if the alias is not 'from' or 'at'.
+    /// Panics if the alias is not 'from' or 'at' of a camera in the scene.
     pub fn cam_translate_z(
         &mut self,
         z: f64,
@@ -513,22 +645,18 @@ impl Scene {
         space: TransformSpace,
         alias: &str,
     ) {
-        assert!(alias == "from" || alias == "at");
-
-        if alias == "from" {
-            self.scene_cam.look_from.translate_z(z, keyframe, it, space);
-        } else {
-            self.scene_cam.look_at.translate_z(z, keyframe, it, space);
-        }
+        self.camera_target(alias)
+            .translate_z(z, keyframe, it, space);
     }
 
-    /// Translates the camera's location, alias of 'from' for the camera location, 'at' for
-    /// where the ray is cast. Note if you call the cam's set_from or set_at functions
-    /// from the scene directly the transform will be lost and any camera animations will have to
-    /// be replaced.
+    /// Translates a camera's location, alias of 'from' for the camera location, 'at' for
+    /// where the ray is cast. These move 'cam', put a camera's name in front such as
+    /// 'close.from' to move another camera. Note if you call the cam's set_from or set_at
+    /// functions from the scene directly the transform will be lost and any camera
+    /// animations will have to be replaced.
     ///
     /// # Panic
-    /// Panics if the alias is not 'from' or 'at'.
+    /// Panics if the alias is not 'from' or 'at' of a camera in the scene.
     pub fn cam_translate_point(
         &mut self,
         p: Point3,
@@ -537,17 +665,70 @@ impl Scene {
         space: TransformSpace,
         alias: &str,
     ) {
-        assert!(alias == "from" || alias == "at");
-
-        if alias == "from" {
-            self.scene_cam
-                .look_from
-                .translate_point(p, keyframe, it, space);
-        } else {
-            self.scene_cam
-                .look_at
-                .translate_point(p, keyframe, it, space);
-        }
+        self.camera_target(alias)
+            .translate_point(p, keyframe, it, space);
+    }
+
+    /// Animates the vertical field of view of the camera named {camera} in degrees, this is
+    /// how zooms are done. Pair it with cam_translate_* on 'from' for a dolly zoom. Calling
+    /// the camera's set_vfov afterwards replaces these keyframes.
+    ///
+    /// # Panic
+    /// Panics if there is no camera named {camera}.
+    pub fn cam_vfov(&mut self, vfov: f64, keyframe: f64, it: InterpolationType, camera: &str) {
+        self.camera_mut(camera)
+            .add_vfov_keyframe(vfov, keyframe, it);
+    }
+
+    /// Animates the focus distance of the camera named {camera} for focus pulls. Calling the
+    /// camera's set_focus_dist afterwards replaces these keyframes.
+    ///
+    /// # Panic
+    /// Panics if there is no camera named {camera}.
+    pub fn cam_focus_dist(
+        &mut self,
+        focus_dist: f64,
+        keyframe: f64,
+        it: InterpolationType,
+        camera: &str,
+    ) {
+        self.camera_mut(camera)
+            .add_focus_dist_keyframe(focus_dist, keyframe, it);
+    }
+
+    /// Animates the defocus angle of the camera named {camera} in degrees. This replaces an
+    /// aperture set with the camera's set_f_number.
+    ///
+    /// # Panic
+    /// Panics if there is no camera named {camera}.
+    pub fn cam_defocus_angle(
+        &mut self,
+        angle: f64,
+        keyframe: f64,
+        it: InterpolationType,
+        camera: &str,
+    ) {
+        self.camera_mut(camera)
+            .add_defocus_angle_keyframe(angle, keyframe, it);
+    }
+
+    /// Animates the roll of the camera named {camera} around the view direction in degrees,
+    /// for dutch angles. Calling the camera's set_roll afterwards replaces these keyframes.
+    ///
+    /// # Panic
+    /// Panics if there is no camera named {camera}.
+    pub fn cam_roll(&mut self, roll: f64, keyframe: f64, it: InterpolationType, camera: &str) {
+        self.camera_mut(camera)
+            .add_roll_keyframe(roll, keyframe, it);
+    }
+
+    /// Animates the up vector of the camera named {camera}. Calling the camera's set_vup
+    /// afterwards replaces these keyframes.
+    ///
+    /// # Panic
+    /// Panics if there is no camera named {camera}.
+    pub fn cam_vup(&mut self, vup: Vec3, keyframe: f64, it: InterpolationType, camera: &str) {
+        self.camera_mut(camera).add_vup_keyframe(vup, keyframe, it);
     }
 }
 
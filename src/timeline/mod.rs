@@ -9,8 +9,11 @@ use crate::{
 
 mod helper_functions;
 mod matrix_builder;
+mod scalar_timeline;
 mod transform_builder;
 
+pub use scalar_timeline::ScalarTimeline;
+
 /// MatrixInfo describes a transform in time
 /// the valid time interval represents the keyframes
 /// for the transform while the transform_description
@@ -250,8 +253,7 @@ impl TransformTimeline {
         let scale_matrix = self
             .scale
             .iter()
-            .filter(|tf| tf.valid_time.is_less(t) || tf.valid_time.contains(t))
-            .next_back()
+            .rfind(|tf| tf.valid_time.is_less(t) || tf.valid_time.contains(t))
             .unwrap()
             .get_matrix_at_time(t);
 
@@ -261,6 +263,28 @@ impl TransformTimeline {
 
         Vector4::from_row_slice(outputs.as_slice())
     }
+
+    /// Returns the times where the motion inside of the window can
+    /// change direction. This is the start and end of the window and
+    /// any keyframe that falls inside of it. Between two of these
+    /// times every transform is linear.
+    pub fn keyframe_times(&self, window: &Interval) -> Vec<f64> {
+        let mut times = vec![window.min(), window.max()];
+
+        for tf in self.scale.iter().chain(&self.rotate).chain(&self.translate) {
+            for time in [tf.valid_time.min(), tf.valid_time.max()] {
+                if window.surrounds(time) {
+                    times.push(time);
+                }
+            }
+        }
+
+        // Back to back keyframes share a time
+        times.sort_by(f64::total_cmp);
+        times.dedup();
+
+        times
+    }
 }
 
 #[cfg(test)]
@@ -329,6 +353,26 @@ mod tests {
         assert!((result[3] - 10.0).abs() < 0.2);
     }
 
+    #[test]
+    fn keyframe_times_in_window() {
+        let mut timeline =
+            TransformTimeline::new(Point3::new(0.0, 0.0, 0.0), Point3::origin(), 1.0);
+
+        timeline.translate_x(4.0, 1.0, InterpolationType::LERP, TransformSpace::World);
+        timeline.translate_x(0.0, 3.0, InterpolationType::LERP, TransformSpace::World);
+
+        // The turn around at 1.0 is inside the window so it has to be checked
+        let times = timeline.keyframe_times(&Interval::new(0.5, 2.0));
+        assert_eq!(times, vec![0.5, 1.0, 2.0]);
+
+        // The furthest the object gets is at the keyframe not the window ends
+        let furthest = times
+            .iter()
+            .map(|&t| timeline.combine_and_compute(t)[0])
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert_eq!(furthest, 4.0);
+    }
+
     #[test]
     fn check_nerp_translate() {
         let mut timeline =
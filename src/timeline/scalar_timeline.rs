@@ -0,0 +1,98 @@
+use crate::timeline::InterpolationType;
+
+/// One value of a scalar timeline and how it is reached
+#[derive(Debug, Clone)]
+struct ScalarKeyframe {
+    time: f64,
+    value: f64,
+    interp: InterpolationType,
+}
+
+/// A single number that changes over time, like a cameras field of
+/// view. Keyframes work like the scale transforms: a LERP keyframe
+/// starts moving at the keyframe before it, a NERP keyframe jumps at
+/// its time.
+#[derive(Debug, Clone)]
+pub struct ScalarTimeline {
+    keyframes: Vec<ScalarKeyframe>,
+}
+
+impl ScalarTimeline {
+    /// A timeline that holds start_value until a keyframe changes it
+    pub fn new(start_value: f64) -> ScalarTimeline {
+        ScalarTimeline {
+            keyframes: vec![ScalarKeyframe {
+                time: 0.0,
+                value: start_value,
+                interp: InterpolationType::NERP,
+            }],
+        }
+    }
+
+    /// Adds a keyframe that reaches value at the time keyframe. A
+    /// keyframe at the same time as an existing one replaces it.
+    ///
+    /// # Panics:
+    /// This panics if the keyframe is before the start of the animation.
+    pub fn add_keyframe(&mut self, value: f64, keyframe: f64, interp: InterpolationType) {
+        assert!(
+            keyframe >= 0.0,
+            "Cannot add a keyframe before the animation start. You tried to add keyframe: {keyframe}"
+        );
+
+        let key = ScalarKeyframe {
+            time: keyframe,
+            value,
+            interp,
+        };
+        match self
+            .keyframes
+            .binary_search_by(|k| k.time.total_cmp(&keyframe))
+        {
+            Ok(index) => self.keyframes[index] = key,
+            Err(index) => self.keyframes.insert(index, key),
+        }
+    }
+
+    /// The value at time t
+    pub fn value_at(&self, t: f64) -> f64 {
+        // The first keyframe after t, the one before it is already reached
+        let next = self.keyframes.partition_point(|k| k.time <= t);
+        if next == 0 {
+            return self.keyframes[0].value;
+        }
+
+        let prev = &self.keyframes[next - 1];
+        match self.keyframes.get(next) {
+            Some(ScalarKeyframe {
+                time,
+                value,
+                interp: InterpolationType::LERP,
+            }) => {
+                let s = (t - prev.time) / (time - prev.time);
+                prev.value + (value - prev.value) * s
+            }
+            _ => prev.value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalar_keyframes() {
+        let mut timeline = ScalarTimeline::new(40.0);
+        timeline.add_keyframe(20.0, 2.0, InterpolationType::LERP);
+        timeline.add_keyframe(60.0, 3.0, InterpolationType::NERP);
+
+        assert_eq!(timeline.value_at(0.0), 40.0);
+        // Halfway through the zoom
+        assert_eq!(timeline.value_at(1.0), 30.0);
+        assert_eq!(timeline.value_at(2.5), 20.0);
+        // The NERP keyframe jumps
+        assert_eq!(timeline.value_at(3.0), 60.0);
+        assert_eq!(timeline.value_at(10.0), 60.0);
+    }
+}
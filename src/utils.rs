@@ -147,6 +147,35 @@ impl Point3 {
         }
     }
 
+    /// Maps two uniform numbers in [0, 1) to a point on the unit
+    /// sphere. Samplers use this in place of random_unit_vector.
+    pub fn unit_vector_from_sample(u: (f64, f64)) -> Vec3 {
+        let z = 1.0 - 2.0 * u.0;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.1;
+
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    /// Maps two uniform numbers in [0, 1) to a point in the unit
+    /// disk. The concentric mapping keeps stratified samples apart.
+    pub fn in_unit_disk_from_sample(u: (f64, f64)) -> Vec3 {
+        let x = 2.0 * u.0 - 1.0;
+        let y = 2.0 * u.1 - 1.0;
+
+        if x == 0.0 && y == 0.0 {
+            return Vec3::origin();
+        }
+
+        let (r, theta) = if x.abs() > y.abs() {
+            (x, (PI / 4.0) * (y / x))
+        } else {
+            (y, (PI / 2.0) - (PI / 4.0) * (x / y))
+        };
+
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
+
     /// Compute the reflection of a vector across the normal
     pub fn reflect(v: &Vec3, norm: &Vec3) -> Vec3 {
         v.clone() - 2.0 * v.dot(norm) * norm.clone()
@@ -367,8 +396,12 @@ impl Color {
 
     /// Generate a random color
     pub fn random_color() -> Color {
-        let mut rng = rand::rng();
+        Color::random_color_from(&mut rand::rng())
+    }
 
+    /// Generate a random color from rng, use a seeded rng to get
+    /// the same colors every time
+    pub fn random_color_from<R: Rng>(rng: &mut R) -> Color {
         let r_rand = rng.random();
         let g_rand = rng.random();
         let b_rand = rng.random();
@@ -379,8 +412,12 @@ impl Color {
     /// Make a random color with a min of low and max of high
     /// Clamps inputs to 0.0 to 1.0
     pub fn random_color_range(low: f64, high: f64) -> Color {
-        let mut rng = rand::rng();
+        Color::random_color_range_from(&mut rand::rng(), low, high)
+    }
 
+    /// Make a random color from rng with a min of low and max of high
+    /// Clamps inputs to 0.0 to 1.0
+    pub fn random_color_range_from<R: Rng>(rng: &mut R, low: f64, high: f64) -> Color {
         let low = low.clamp(0.0, 1.0);
         let high = high.clamp(0.0, 1.0);
 
@@ -403,8 +440,9 @@ impl Color {
         self.rgb.z()
     }
 
-    // Helper function for output
-    fn linear_to_gamma(linear_component: f64) -> f64 {
+    /// Helper function for output, converts a linear
+    /// component to gamma 2 space
+    pub fn linear_to_gamma(linear_component: f64) -> f64 {
         linear_component.sqrt()
     }
 }
@@ -603,6 +641,133 @@ impl Div<f64> for Color {
     }
 }
 
+/// Radiance is the light carried along a ray. Unlike Color it
+/// has no upper bound, so bright skies and highlights keep their
+/// energy until the camera tone maps the finished image.
+#[derive(Debug, PartialEq)]
+pub struct Radiance {
+    rgb: Point3,
+}
+
+impl Radiance {
+    pub fn new(r: f64, g: f64, b: f64) -> Radiance {
+        Radiance {
+            rgb: Point3 { values: (r, g, b) },
+        }
+    }
+
+    /// No light at all
+    pub fn black() -> Radiance {
+        Radiance::new(0.0, 0.0, 0.0)
+    }
+
+    pub fn r(&self) -> f64 {
+        self.rgb.x()
+    }
+
+    pub fn g(&self) -> f64 {
+        self.rgb.y()
+    }
+
+    pub fn b(&self) -> f64 {
+        self.rgb.z()
+    }
+
+    /// The Rec. 709 luminance of the radiance
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
+    /// The brightest of the three channels
+    pub fn max_channel(&self) -> f64 {
+        self.r().max(self.g()).max(self.b())
+    }
+
+    /// Clamps each channel into a displayable Color. Channels that
+    /// are NaN or infinite become black, a single bad sample should
+    /// not stop the render.
+    pub fn clamp_to_color(&self) -> Color {
+        let clamp = |c: f64| if c.is_finite() { c.clamp(0.0, 1.0) } else { 0.0 };
+        Color::new(clamp(self.r()), clamp(self.g()), clamp(self.b()))
+    }
+}
+
+impl Clone for Radiance {
+    fn clone(&self) -> Self {
+        Radiance {
+            rgb: self.rgb.clone(),
+        }
+    }
+}
+
+impl From<Color> for Radiance {
+    fn from(color: Color) -> Self {
+        Radiance { rgb: color.rgb }
+    }
+}
+
+impl AddAssign for Radiance {
+    fn add_assign(&mut self, rhs: Self) {
+        self.rgb += rhs.rgb;
+    }
+}
+
+impl Add for Radiance {
+    type Output = Radiance;
+
+    fn add(self, rhs: Radiance) -> Self::Output {
+        Radiance {
+            rgb: self.rgb + rhs.rgb,
+        }
+    }
+}
+
+impl Mul<f64> for Radiance {
+    type Output = Radiance;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Radiance {
+            rgb: self.rgb * rhs,
+        }
+    }
+}
+
+impl Mul<Radiance> for f64 {
+    type Output = Radiance;
+
+    fn mul(self, rhs: Radiance) -> Self::Output {
+        rhs * self
+    }
+}
+
+/// Filters light channel by channel, a path carries some of the
+/// light it finds back to the camera like this.
+impl Mul for Radiance {
+    type Output = Radiance;
+
+    fn mul(self, rhs: Radiance) -> Self::Output {
+        Radiance::new(self.r() * rhs.r(), self.g() * rhs.g(), self.b() * rhs.b())
+    }
+}
+
+/// Attenuates light by a surface color, which is how a material
+/// filters the light bouncing off of it.
+impl Mul<Radiance> for Color {
+    type Output = Radiance;
+
+    fn mul(self, rhs: Radiance) -> Self::Output {
+        Radiance::new(self.r() * rhs.r(), self.g() * rhs.g(), self.b() * rhs.b())
+    }
+}
+
+impl Div<f64> for Radiance {
+    type Output = Radiance;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        self * (1.0 / rhs)
+    }
+}
+
 /// Randomly generate a color
 pub fn random_color() -> Color {
     let v = Vec3::random_vec3();
@@ -696,6 +861,107 @@ impl Interval {
     pub const UNIVERSE: Interval = Interval::new(-f64::INFINITY, f64::INFINITY);
 }
 
+/// A grid of weights that 2D samples are mapped onto, every cell is
+/// picked in proportion to its weight. A row is picked first, then
+/// a cell inside of it.
+#[derive(Debug, Clone)]
+pub(crate) struct Distribution2D {
+    width: usize,
+    height: usize,
+    /// Running sum of the rows, normalized to end at 1
+    rows: Vec<f64>,
+    /// Running sum inside each row, normalized to end at 1
+    columns: Vec<f64>,
+    /// The weight of each cell over the average weight
+    density: Vec<f64>,
+}
+
+impl Distribution2D {
+    /// Builds the distribution from the weights of the cells, row by
+    /// row. Returns None if every weight is 0 or a weight is NaN or
+    /// infinite.
+    pub(crate) fn new(width: usize, height: usize, weights: Vec<f64>) -> Option<Distribution2D> {
+        let mut rows = Vec::with_capacity(height);
+        let mut columns = Vec::with_capacity(width * height);
+        let mut total = 0.0;
+
+        for row in weights.chunks(width) {
+            let mut sum = 0.0;
+            for weight in row {
+                sum += weight;
+                columns.push(sum);
+            }
+            // An empty row is never picked, so it can stay unnormalized
+            if sum > 0.0 {
+                let start = columns.len() - width;
+                columns[start..].iter_mut().for_each(|c| *c /= sum);
+            }
+
+            total += sum;
+            rows.push(total);
+        }
+
+        if !total.is_finite() || total <= 0.0 {
+            return None;
+        }
+        rows.iter_mut().for_each(|r| *r /= total);
+
+        let average = total / (width * height) as f64;
+        let density = weights.iter().map(|w| w / average).collect();
+
+        Some(Distribution2D {
+            width,
+            height,
+            rows,
+            columns,
+            density,
+        })
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Maps a 2D sample to a point of the grid, cell (x, y) covers
+    /// [x, x + 1) by [y, y + 1)
+    pub(crate) fn sample(&self, u: (f64, f64)) -> (f64, f64) {
+        let (y, fy) = pick(&self.rows, u.1);
+        let row = &self.columns[y * self.width..(y + 1) * self.width];
+        let (x, fx) = pick(row, u.0);
+
+        (x as f64 + fx, y as f64 + fy)
+    }
+
+    /// How likely sample is to land in cell (x, y) compared to
+    /// picking every cell evenly
+    pub(crate) fn density(&self, x: usize, y: usize) -> f64 {
+        let x = x.min(self.width - 1);
+        let y = y.min(self.height - 1);
+
+        self.density[y * self.width + x]
+    }
+}
+
+/// Finds the entry of a running sum that u lands in, along with
+/// how far into the entry it is
+fn pick(cdf: &[f64], u: f64) -> (usize, f64) {
+    let index = cdf.partition_point(|&c| c <= u).min(cdf.len() - 1);
+    let start = if index == 0 { 0.0 } else { cdf[index - 1] };
+    let width = cdf[index] - start;
+
+    let fraction = if width > 0.0 {
+        ((u - start) / width).clamp(0.0, 1.0)
+    } else {
+        0.5
+    };
+
+    (index, fraction)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -804,6 +1070,30 @@ mod tests {
         assert_eq!(r, y);
     }
 
+    #[test]
+    fn distribution_rejects_bad_weights() {
+        assert!(Distribution2D::new(2, 1, vec![1.0, 3.0]).is_some());
+        assert!(Distribution2D::new(2, 1, vec![0.0, 0.0]).is_none());
+        assert!(Distribution2D::new(2, 1, vec![1.0, f64::NAN]).is_none());
+        assert!(Distribution2D::new(2, 1, vec![1.0, f64::INFINITY]).is_none());
+    }
+
+    #[test]
+    fn radiance_is_unbounded() {
+        let sky = Radiance::new(4.0, 2.0, 0.5);
+        let lit = Color::new(0.5, 0.5, 0.5) * (sky.clone() + sky);
+
+        assert_eq!(lit, Radiance::new(4.0, 2.0, 0.5));
+        assert_eq!(lit.clamp_to_color(), Color::new(1.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn broken_radiance_clamps_to_black() {
+        let broken = Radiance::new(f64::NAN, f64::INFINITY, 0.5);
+
+        assert_eq!(broken.clamp_to_color(), Color::new(0.0, 0.0, 0.5));
+    }
+
     #[test]
     fn degrees_convert_test() {
         let d = Degrees::new(59.2958);